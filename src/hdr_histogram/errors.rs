@@ -0,0 +1,357 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// Errors that can occur when creating a histogram.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CreationError {
+    /// Lowest discernible value must be >= 1.
+    LowIsZero,
+    /// Highest trackable value must be >= 2 * lowest discernible value.
+    HighLessThanTwiceLow,
+    /// Number of significant digits must be in the range [0, 5].
+    SigFigExceedsMax,
+    /// The lowest discernible value is so large that the requested number of significant digits
+    /// can't be represented beyond it within 64 bits.
+    CannotRepresentSigFigBeyondLow,
+    /// A `DoubleHistogram`'s highest to lowest value ratio must be >= 2.
+    HighToLowRatioTooSmall,
+    /// A `DoubleHistogram`'s highest to lowest value ratio times 10^(number of significant
+    /// digits) must be less than 2^61.
+    RatioAndSigFigExceedRange,
+    /// The value unit ratio must be positive and finite.
+    InvalidValueUnitRatio,
+}
+
+/// Errors that can occur when recording a value.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum RecordError {
+    /// The value to record is not representable in this histogram.
+    ValueOutOfRange,
+    /// Recording would overflow the count type at the value's index.
+    CountOverflow,
+}
+
+/// Errors that can occur when looking up counts.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum QueryError {
+    /// The index does not map to a position in the counts array.
+    IndexOutOfRange,
+}
+
+/// Errors that can occur when adding one histogram to another.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum AdditionError {
+    /// The other histogram contains values beyond what this histogram can hold, and this
+    /// histogram isn't auto-resizing.
+    OtherAddendValueExceedsRange,
+    /// Adding would overflow the count type at some value.
+    CountOverflow,
+}
+
+/// Errors that can occur when subtracting one histogram from another.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SubtractionError {
+    /// The other histogram contains values beyond what this histogram can hold, so they can't
+    /// have been recorded here.
+    SubtrahendValueExceedsMinuendRange,
+    /// The other histogram has a larger count than this histogram at some value, which would
+    /// leave a negative count.
+    SubtrahendCountExceedsMinuendCount,
+}
+
+/// Errors that can occur when shifting a histogram's values.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ShiftError {
+    /// Shifting left would move the max value beyond what the histogram can hold.
+    Overflow,
+    /// Shifting right would move a non-zero value into the lower half of bucket 0 or below, where
+    /// it would lose precision.
+    Underflow,
+}
+
+/// Errors that can occur when encoding a histogram.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EncodeError {
+    /// A count is larger than `i64::max_value()`, the largest count the encoding can express.
+    CountNotEncodable,
+}
+
+/// Errors that can occur when decoding a histogram.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DecodeError {
+    /// The cookie at the start of the input doesn't identify a supported encoding.
+    InvalidCookie,
+    /// The header describes a histogram that can't be created.
+    InvalidParameters(CreationError),
+    /// The header's normalizing index offset doesn't fit within the counts array.
+    InvalidNormalizingIndexOffset,
+    /// The input ends before the header or the payload length it declares.
+    Truncated,
+    /// The payload has more counts than the histogram described by the header can hold.
+    EncodedArrayTooLong,
+    /// A count is too big for the histogram's count type.
+    CountExceedsTypeMax,
+    /// The compressed payload isn't valid zlib data.
+    DecompressionFailed,
+}
+
+/// Errors that can occur when writing an interval log.
+#[derive(Debug)]
+pub enum IntervalLogWriteError {
+    /// Writing to the underlying writer failed.
+    Io(io::Error),
+    /// The histogram couldn't be encoded.
+    Encode(EncodeError),
+    /// Tags can't be empty or contain commas, whitespace or line breaks.
+    InvalidTag,
+}
+
+/// Errors that can occur when reading an interval log.
+#[derive(Debug)]
+pub enum IntervalLogReadError {
+    /// Reading from the underlying reader failed.
+    Io(io::Error),
+    /// A line is neither a comment, the legend, nor a well-formed interval.
+    MalformedLine,
+    /// An interval's histogram isn't valid base64.
+    InvalidBase64,
+    /// An interval's histogram couldn't be decoded.
+    Decode(DecodeError),
+}
+
+impl From<QueryError> for RecordError {
+    fn from(err: QueryError) -> RecordError {
+        match err {
+            QueryError::IndexOutOfRange => RecordError::ValueOutOfRange
+        }
+    }
+}
+
+impl From<QueryError> for AdditionError {
+    fn from(err: QueryError) -> AdditionError {
+        match err {
+            QueryError::IndexOutOfRange => AdditionError::OtherAddendValueExceedsRange
+        }
+    }
+}
+
+impl From<RecordError> for AdditionError {
+    fn from(err: RecordError) -> AdditionError {
+        match err {
+            RecordError::ValueOutOfRange => AdditionError::OtherAddendValueExceedsRange,
+            RecordError::CountOverflow => AdditionError::CountOverflow
+        }
+    }
+}
+
+impl From<QueryError> for SubtractionError {
+    fn from(err: QueryError) -> SubtractionError {
+        match err {
+            QueryError::IndexOutOfRange => SubtractionError::SubtrahendValueExceedsMinuendRange
+        }
+    }
+}
+
+impl From<ShiftError> for RecordError {
+    fn from(err: ShiftError) -> RecordError {
+        match err {
+            ShiftError::Overflow | ShiftError::Underflow => RecordError::ValueOutOfRange
+        }
+    }
+}
+
+impl From<CreationError> for DecodeError {
+    fn from(err: CreationError) -> DecodeError {
+        DecodeError::InvalidParameters(err)
+    }
+}
+
+impl From<io::Error> for IntervalLogWriteError {
+    fn from(err: io::Error) -> IntervalLogWriteError {
+        IntervalLogWriteError::Io(err)
+    }
+}
+
+impl From<EncodeError> for IntervalLogWriteError {
+    fn from(err: EncodeError) -> IntervalLogWriteError {
+        IntervalLogWriteError::Encode(err)
+    }
+}
+
+impl From<io::Error> for IntervalLogReadError {
+    fn from(err: io::Error) -> IntervalLogReadError {
+        IntervalLogReadError::Io(err)
+    }
+}
+
+impl From<DecodeError> for IntervalLogReadError {
+    fn from(err: DecodeError) -> IntervalLogReadError {
+        IntervalLogReadError::Decode(err)
+    }
+}
+
+impl fmt::Display for CreationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CreationError::LowIsZero =>
+                write!(f, "lowest discernible value must be >= 1"),
+            CreationError::HighLessThanTwiceLow =>
+                write!(f, "highest trackable value must be >= 2 * lowest discernible value"),
+            CreationError::SigFigExceedsMax =>
+                write!(f, "number of significant digits must be <= 5"),
+            CreationError::CannotRepresentSigFigBeyondLow =>
+                write!(f, "cannot represent the number of significant digits beyond the lowest \
+                    discernible value"),
+            CreationError::HighToLowRatioTooSmall =>
+                write!(f, "highest to lowest value ratio must be >= 2"),
+            CreationError::RatioAndSigFigExceedRange =>
+                write!(f, "highest to lowest value ratio and number of significant digits need \
+                    more than 61 bits"),
+            CreationError::InvalidValueUnitRatio =>
+                write!(f, "value unit ratio must be positive and finite"),
+        }
+    }
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RecordError::ValueOutOfRange =>
+                write!(f, "value is outside the range covered by the histogram"),
+            RecordError::CountOverflow =>
+                write!(f, "count at value would overflow the count type"),
+        }
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            QueryError::IndexOutOfRange => write!(f, "index out of covered range"),
+        }
+    }
+}
+
+impl fmt::Display for AdditionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AdditionError::OtherAddendValueExceedsRange =>
+                write!(f, "other histogram has values beyond the range of this histogram"),
+            AdditionError::CountOverflow =>
+                write!(f, "count at value would overflow the count type"),
+        }
+    }
+}
+
+impl fmt::Display for SubtractionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SubtractionError::SubtrahendValueExceedsMinuendRange =>
+                write!(f, "other histogram has values beyond the range of this histogram"),
+            SubtractionError::SubtrahendCountExceedsMinuendCount =>
+                write!(f, "other histogram has a larger count than this histogram at some value"),
+        }
+    }
+}
+
+impl fmt::Display for ShiftError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ShiftError::Overflow =>
+                write!(f, "shifting would move recorded values beyond the range of the histogram"),
+            ShiftError::Underflow =>
+                write!(f, "shifting would lose precision of recorded values"),
+        }
+    }
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EncodeError::CountNotEncodable =>
+                write!(f, "count is too large to encode"),
+        }
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecodeError::InvalidCookie =>
+                write!(f, "input does not start with a supported encoding cookie"),
+            DecodeError::InvalidParameters(ref err) =>
+                write!(f, "invalid histogram parameters: {}", err),
+            DecodeError::InvalidNormalizingIndexOffset =>
+                write!(f, "normalizing index offset is outside the counts array"),
+            DecodeError::Truncated =>
+                write!(f, "input is shorter than the encoded histogram"),
+            DecodeError::EncodedArrayTooLong =>
+                write!(f, "encoded counts do not fit in the histogram"),
+            DecodeError::CountExceedsTypeMax =>
+                write!(f, "encoded count is too large for the count type"),
+            DecodeError::DecompressionFailed =>
+                write!(f, "compressed payload could not be decompressed"),
+        }
+    }
+}
+
+impl fmt::Display for IntervalLogWriteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IntervalLogWriteError::Io(ref err) => write!(f, "could not write log: {}", err),
+            IntervalLogWriteError::Encode(ref err) =>
+                write!(f, "could not encode histogram: {}", err),
+            IntervalLogWriteError::InvalidTag =>
+                write!(f, "tag must be non-empty without commas, whitespace or line breaks"),
+        }
+    }
+}
+
+impl fmt::Display for IntervalLogReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IntervalLogReadError::Io(ref err) => write!(f, "could not read log: {}", err),
+            IntervalLogReadError::MalformedLine => write!(f, "malformed log line"),
+            IntervalLogReadError::InvalidBase64 => write!(f, "histogram is not valid base64"),
+            IntervalLogReadError::Decode(ref err) =>
+                write!(f, "could not decode histogram: {}", err),
+        }
+    }
+}
+
+impl Error for CreationError {}
+
+impl Error for RecordError {}
+
+impl Error for QueryError {}
+
+impl Error for AdditionError {}
+
+impl Error for SubtractionError {}
+
+impl Error for ShiftError {}
+
+impl Error for EncodeError {}
+
+impl Error for DecodeError {}
+
+impl Error for IntervalLogWriteError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            IntervalLogWriteError::Io(ref err) => Some(err),
+            IntervalLogWriteError::Encode(ref err) => Some(err),
+            IntervalLogWriteError::InvalidTag => None,
+        }
+    }
+}
+
+impl Error for IntervalLogReadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            IntervalLogReadError::Io(ref err) => Some(err),
+            IntervalLogReadError::Decode(ref err) => Some(err),
+            IntervalLogReadError::MalformedLine | IntervalLogReadError::InvalidBase64 => None,
+        }
+    }
+}
@@ -0,0 +1,367 @@
+//! Reading and writing interval logs: a time series of histograms, one per line, in the `.hlog`
+//! format used by the Java `HistogramLogWriter` and `HistogramLogReader`.
+//!
+//! A log looks like this:
+//!
+//! ```text
+//! #[Histogram log format version 1.3]
+//! #[StartTime: 1441812279.474 (seconds since epoch)]
+//! #[BaseTime: 1441812279.000 (seconds since epoch)]
+//! "StartTimestamp","Interval_Length","Interval_Max","Interval_Compressed_Histogram"
+//! 0.127,1.007,2.769,HISTFAAAAEV42pNpmSzMwMCgyAABTBDKT4GBgdnNYMcCBvsPEBEJISEuATEZMQ4uKS4...
+//! Tag=db,1.134,0.999,0.442,HISTFAAAAEF42pNpmSzMwMCgwAABTBDKT4GBgdnNYMcCBvsPEBEJISEuATEZMQ4...
+//! ```
+//!
+//! Interval start timestamps are in seconds relative to the base time (0 if there is none), and
+//! each histogram is base64 of its compressed V2 encoding.
+
+use std::f64;
+use std::io::BufRead;
+use std::io::Write;
+use base64;
+
+use hdr_histogram::errors::*;
+use hdr_histogram::simple_hdr_histogram::*;
+
+const LOG_FORMAT_VERSION: &str = "1.3";
+const START_TIME_PREFIX: &str = "#[StartTime: ";
+const BASE_TIME_PREFIX: &str = "#[BaseTime: ";
+const LEGEND: &str =
+    "\"StartTimestamp\",\"Interval_Length\",\"Interval_Max\",\"Interval_Compressed_Histogram\"";
+const TAG_PREFIX: &str = "Tag=";
+
+/// If the first interval timestamp is more than this many seconds before the start time and
+/// there's no base time, timestamps are taken to be relative to the start time rather than
+/// absolute. This matches the Java reader.
+const RELATIVE_TIMESTAMP_THRESHOLD_SECS: f64 = 365.0 * 24.0 * 3600.0;
+
+/// Writes an interval log.
+///
+/// Call the header methods (`write_log_format_version`, `write_start_time`, `write_base_time`,
+/// `write_legend`) first, then `write_histogram` for each interval.
+#[derive(Debug)]
+pub struct IntervalLogWriter<W: Write> {
+    writer: W,
+    base_time: f64,
+    max_value_unit_ratio: f64,
+    compression_level: u32,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> IntervalLogWriter<W> {
+    /// Create a writer with no base time, a max value unit ratio of 1,000,000 (so that
+    /// nanosecond values have their max written in milliseconds) and the best compression.
+    pub fn new(writer: W) -> IntervalLogWriter<W> {
+        IntervalLogWriter {
+            writer: writer,
+            base_time: 0.0,
+            max_value_unit_ratio: 1_000_000.0,
+            compression_level: MAX_COMPRESSION_LEVEL,
+            buf: Vec::new(),
+        }
+    }
+
+    /// The interval max column is the highest equivalent value of the histogram's max divided by
+    /// this ratio. It's only there for people reading the log; readers get the max from the
+    /// histogram itself.
+    pub fn set_max_value_unit_ratio(&mut self, ratio: f64) {
+        self.max_value_unit_ratio = ratio;
+    }
+
+    /// Compression level for the encoded histograms, from 0 to `MAX_COMPRESSION_LEVEL`.
+    pub fn set_compression_level(&mut self, level: u32) {
+        self.compression_level = level;
+    }
+
+    pub fn write_log_format_version(&mut self) -> Result<(), IntervalLogWriteError> {
+        writeln!(self.writer, "#[Histogram log format version {}]", LOG_FORMAT_VERSION)?;
+        Ok(())
+    }
+
+    /// start_time: seconds since the epoch
+    pub fn write_start_time(&mut self, start_time: f64) -> Result<(), IntervalLogWriteError> {
+        writeln!(self.writer, "{}{:.3} (seconds since epoch)]", START_TIME_PREFIX, start_time)?;
+        Ok(())
+    }
+
+    /// Interval timestamps written after this are relative to `base_time`.
+    ///
+    /// base_time: seconds since the epoch
+    pub fn write_base_time(&mut self, base_time: f64) -> Result<(), IntervalLogWriteError> {
+        writeln!(self.writer, "{}{:.3} (seconds since epoch)]", BASE_TIME_PREFIX, base_time)?;
+        self.base_time = base_time;
+        Ok(())
+    }
+
+    pub fn write_legend(&mut self) -> Result<(), IntervalLogWriteError> {
+        writeln!(self.writer, "{}", LEGEND)?;
+        Ok(())
+    }
+
+    /// Write a comment line. Each line of `comment` gets its own `#` prefix.
+    pub fn write_comment(&mut self, comment: &str) -> Result<(), IntervalLogWriteError> {
+        for line in comment.lines() {
+            writeln!(self.writer, "#{}", line)?;
+        }
+        Ok(())
+    }
+
+    /// Write one interval's histogram.
+    ///
+    /// start_timestamp: seconds since the epoch
+    /// interval_length: seconds
+    /// tag: identifies which of several interleaved series the interval belongs to
+    pub fn write_histogram<T: HistogramCount>(&mut self, h: &SimpleHdrHistogram<T>,
+                                              start_timestamp: f64, interval_length: f64,
+                                              tag: Option<&str>)
+                                              -> Result<(), IntervalLogWriteError> {
+        if let Some(t) = tag {
+            if !is_valid_tag(t) {
+                return Err(IntervalLogWriteError::InvalidTag);
+            }
+        }
+
+        // Encode before writing anything, so that a failure doesn't leave half a line behind.
+        self.buf.clear();
+        h.encode_compressed_into(&mut self.buf, self.compression_level)?;
+
+        // Java writes the highest equivalent value of the max, like its getMaxValue
+        let max = match h.get_max() {
+            0 => 0,
+            max => h.highest_equivalent_value(max)
+        };
+        if let Some(t) = tag {
+            write!(self.writer, "{}{},", TAG_PREFIX, t)?;
+        }
+        writeln!(self.writer, "{:.3},{:.3},{:.3},{}", start_timestamp - self.base_time,
+                 interval_length, max as f64 / self.max_value_unit_ratio,
+                 base64::encode(&self.buf))?;
+        Ok(())
+    }
+
+    /// Flush and return the underlying writer.
+    pub fn into_inner(mut self) -> Result<W, IntervalLogWriteError> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+fn is_valid_tag(tag: &str) -> bool {
+    !tag.is_empty() && !tag.contains(|c: char| c == ',' || c.is_whitespace())
+}
+
+/// One interval read from a log.
+#[derive(Debug)]
+pub struct IntervalLogHistogram<T: HistogramCount> {
+    tag: Option<String>,
+    start_timestamp: f64,
+    interval_length: f64,
+    histogram: SimpleHdrHistogram<T>,
+}
+
+impl<T: HistogramCount> IntervalLogHistogram<T> {
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    /// Seconds since the epoch, or since the log's start time if the log's timestamps are
+    /// relative and it has no start time.
+    pub fn start_timestamp(&self) -> f64 {
+        self.start_timestamp
+    }
+
+    /// Seconds
+    pub fn interval_length(&self) -> f64 {
+        self.interval_length
+    }
+
+    pub fn histogram(&self) -> &SimpleHdrHistogram<T> {
+        &self.histogram
+    }
+
+    pub fn into_histogram(self) -> SimpleHdrHistogram<T> {
+        self.histogram
+    }
+}
+
+/// Which intervals `IntervalLogReader::next_matching_histogram` should return.
+///
+/// Defaults to all intervals.
+#[derive(Debug, Clone)]
+pub struct IntervalLogFilter {
+    range_start: f64,
+    range_end: f64,
+    absolute: bool,
+    /// None for any tag, Some(None) for only untagged intervals
+    tag: Option<Option<String>>,
+}
+
+impl IntervalLogFilter {
+    pub fn new() -> IntervalLogFilter {
+        IntervalLogFilter {
+            range_start: f64::NEG_INFINITY,
+            range_end: f64::INFINITY,
+            absolute: false,
+            tag: None,
+        }
+    }
+
+    /// Only intervals starting between `range_start` and `range_end` seconds (inclusive) after
+    /// the log's start time.
+    pub fn range(mut self, range_start: f64, range_end: f64) -> IntervalLogFilter {
+        self.range_start = range_start;
+        self.range_end = range_end;
+        self.absolute = false;
+        self
+    }
+
+    /// Only intervals starting between `range_start` and `range_end` seconds (inclusive) since
+    /// the epoch.
+    pub fn absolute_range(mut self, range_start: f64, range_end: f64) -> IntervalLogFilter {
+        self.range_start = range_start;
+        self.range_end = range_end;
+        self.absolute = true;
+        self
+    }
+
+    /// Only intervals with exactly this tag. `None` selects untagged intervals.
+    pub fn tag(mut self, tag: Option<&str>) -> IntervalLogFilter {
+        self.tag = Some(tag.map(|t| t.to_owned()));
+        self
+    }
+}
+
+impl Default for IntervalLogFilter {
+    fn default() -> IntervalLogFilter {
+        IntervalLogFilter::new()
+    }
+}
+
+/// Reads an interval log, one interval at a time.
+#[derive(Debug)]
+pub struct IntervalLogReader<R: BufRead> {
+    reader: R,
+    line: String,
+    start_time: Option<f64>,
+    base_time: Option<f64>,
+}
+
+impl<R: BufRead> IntervalLogReader<R> {
+    pub fn new(reader: R) -> IntervalLogReader<R> {
+        IntervalLogReader {
+            reader: reader,
+            line: String::new(),
+            start_time: None,
+            base_time: None,
+        }
+    }
+
+    /// The log's start time in seconds since the epoch. If the log has no start time, this is the
+    /// first interval's timestamp once an interval has been read.
+    pub fn start_time(&self) -> Option<f64> {
+        self.start_time
+    }
+
+    /// The base time that interval timestamps are relative to, in seconds since the epoch. If the
+    /// log has no base time, this is deduced when the first interval is read.
+    pub fn base_time(&self) -> Option<f64> {
+        self.base_time
+    }
+
+    /// Read the next interval, or `None` at the end of the log.
+    pub fn next_histogram<T: HistogramCount>(&mut self)
+            -> Result<Option<IntervalLogHistogram<T>>, IntervalLogReadError> {
+        self.next_matching_histogram(&IntervalLogFilter::new())
+    }
+
+    /// Read the next interval selected by `filter`, or `None` at the end of the log. Since
+    /// intervals are in time order, this also returns `None` at the first interval starting after
+    /// the filter's range.
+    pub fn next_matching_histogram<T: HistogramCount>(&mut self, filter: &IntervalLogFilter)
+            -> Result<Option<IntervalLogHistogram<T>>, IntervalLogReadError> {
+        loop {
+            self.line.clear();
+            if self.reader.read_line(&mut self.line)? == 0 {
+                return Ok(None);
+            }
+
+            let line = self.line.trim_end();
+            if let Some(rest) = line.strip_prefix(START_TIME_PREFIX) {
+                self.start_time = Some(parse_header_time(rest)?);
+                continue;
+            }
+            if let Some(rest) = line.strip_prefix(BASE_TIME_PREFIX) {
+                self.base_time = Some(parse_header_time(rest)?);
+                continue;
+            }
+            if line.is_empty() || line.starts_with('#') || line.starts_with("\"StartTimestamp\"") {
+                continue;
+            }
+
+            let (tag, fields) = if let Some(rest) = line.strip_prefix(TAG_PREFIX) {
+                match rest.find(',') {
+                    Some(i) => (Some(&rest[..i]), &rest[i + 1..]),
+                    None => return Err(IntervalLogReadError::MalformedLine)
+                }
+            } else {
+                (None, line)
+            };
+
+            let mut fields = fields.split(',');
+            let timestamp = parse_field(fields.next())?;
+            let interval_length = parse_field(fields.next())?;
+            // the max is also in the histogram, so its column is only checked for presence
+            let _max = fields.next().ok_or(IntervalLogReadError::MalformedLine)?;
+            let encoded = fields.next().ok_or(IntervalLogReadError::MalformedLine)?;
+            if fields.next().is_some() {
+                return Err(IntervalLogReadError::MalformedLine);
+            }
+
+            let start_time = *self.start_time.get_or_insert(timestamp);
+            let base_time = *self.base_time.get_or_insert(
+                if timestamp < start_time - RELATIVE_TIMESTAMP_THRESHOLD_SECS {
+                    start_time
+                } else {
+                    0.0
+                });
+
+            let absolute_start_timestamp = timestamp + base_time;
+            let range_timestamp = if filter.absolute {
+                absolute_start_timestamp
+            } else {
+                absolute_start_timestamp - start_time
+            };
+            if range_timestamp < filter.range_start {
+                continue;
+            }
+            if range_timestamp > filter.range_end {
+                return Ok(None);
+            }
+            if let Some(ref wanted) = filter.tag {
+                if wanted.as_deref() != tag {
+                    continue;
+                }
+            }
+
+            let compressed = base64::decode(encoded)
+                .map_err(|_| IntervalLogReadError::InvalidBase64)?;
+            let histogram = SimpleHdrHistogram::decode_compressed(&compressed)?;
+
+            return Ok(Some(IntervalLogHistogram {
+                tag: tag.map(|t| t.to_owned()),
+                start_timestamp: absolute_start_timestamp,
+                interval_length: interval_length,
+                histogram: histogram,
+            }));
+        }
+    }
+}
+
+/// Parse the seconds at the start of e.g. "1441812279.474 (seconds since epoch), Wed Sep 09..."
+fn parse_header_time(rest: &str) -> Result<f64, IntervalLogReadError> {
+    parse_field(rest.split_whitespace().next())
+}
+
+fn parse_field(field: Option<&str>) -> Result<f64, IntervalLogReadError> {
+    field.and_then(|f| f.trim().parse().ok()).ok_or(IntervalLogReadError::MalformedLine)
+}
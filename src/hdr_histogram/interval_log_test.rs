@@ -0,0 +1,328 @@
+use std::io::Cursor;
+
+use hdr_histogram::errors::*;
+use hdr_histogram::interval_log::*;
+use hdr_histogram::simple_hdr_histogram::*;
+
+fn histo_with(values: &[u64]) -> SimpleHdrHistogram<u64> {
+    let mut h = SimpleHdrHistogram::<u64>::new(1, 3600 * 1000 * 1000, 3);
+    for &v in values {
+        h.record_single_value(v).unwrap();
+    }
+    h
+}
+
+fn write_log<F>(write_intervals: F) -> String
+        where F: Fn(&mut IntervalLogWriter<Vec<u8>>) -> Result<(), IntervalLogWriteError> {
+    let mut writer = IntervalLogWriter::new(Vec::new());
+    write_intervals(&mut writer).unwrap();
+    String::from_utf8(writer.into_inner().unwrap()).unwrap()
+}
+
+fn read_all(log: &str, filter: &IntervalLogFilter) -> Vec<IntervalLogHistogram<u64>> {
+    let mut reader = IntervalLogReader::new(Cursor::new(log.as_bytes()));
+    let mut intervals = Vec::new();
+    while let Some(i) = reader.next_matching_histogram(filter).unwrap() {
+        intervals.push(i);
+    }
+    intervals
+}
+
+#[test]
+fn write_headers() {
+    let log = write_log(|w| {
+        w.write_log_format_version()?;
+        w.write_start_time(1441812279.474)?;
+        w.write_base_time(1441812279.0)?;
+        w.write_legend()?;
+        w.write_comment("first\nsecond")
+    });
+
+    assert_eq!("#[Histogram log format version 1.3]\n\
+                #[StartTime: 1441812279.474 (seconds since epoch)]\n\
+                #[BaseTime: 1441812279.000 (seconds since epoch)]\n\
+                \"StartTimestamp\",\"Interval_Length\",\"Interval_Max\",\"Interval_Compressed_Histogram\"\n\
+                #first\n\
+                #second\n", log);
+}
+
+#[test]
+fn write_histogram_line() {
+    let h = histo_with(&[1_000_000, 2_500_000]);
+    let log = write_log(|w| {
+        w.write_base_time(100.0)?;
+        w.write_histogram(&h, 100.127, 1.0, None)?;
+        w.write_histogram(&h, 101.5, 0.25, Some("db"))
+    });
+
+    let lines: Vec<&str> = log.lines().collect();
+    assert_eq!(3, lines.len());
+    // 2,500,000's highest equivalent value is 2,500,607
+    assert!(lines[1].starts_with("0.127,1.000,2.501,HISTF"), "{}", lines[1]);
+    assert!(lines[2].starts_with("Tag=db,1.500,0.250,2.501,HISTF"), "{}", lines[2]);
+}
+
+#[test]
+fn write_empty_histogram_max() {
+    let log = write_log(|w| w.write_histogram(&histo_with(&[]), 0.0, 1.0, None));
+
+    assert!(log.starts_with("0.000,1.000,0.000,HISTF"), "{}", log);
+}
+
+#[test]
+fn write_invalid_tag() {
+    let h = histo_with(&[1]);
+    let mut writer = IntervalLogWriter::new(Vec::new());
+
+    for tag in &["", "a,b", "a b", "a\nb"] {
+        match writer.write_histogram(&h, 0.0, 1.0, Some(tag)) {
+            Err(IntervalLogWriteError::InvalidTag) => {},
+            other => panic!("tag {:?}: {:?}", tag, other)
+        }
+    }
+    assert!(writer.into_inner().unwrap().is_empty());
+}
+
+#[test]
+fn write_unencodable_histogram() {
+    let mut h = histo_with(&[]);
+    h.record_value_with_count(1000, u64::max_value()).unwrap();
+    let mut writer = IntervalLogWriter::new(Vec::new());
+
+    for tag in &[None, Some("db")] {
+        match writer.write_histogram(&h, 0.0, 1.0, *tag) {
+            Err(IntervalLogWriteError::Encode(EncodeError::CountNotEncodable)) => {},
+            other => panic!("tag {:?}: {:?}", tag, other)
+        }
+    }
+    assert!(writer.into_inner().unwrap().is_empty());
+}
+
+#[test]
+fn round_trip() {
+    let first = histo_with(&[1000, 2000, 3_000_000]);
+    let second = histo_with(&[]);
+    let third = histo_with(&[7]);
+    let log = write_log(|w| {
+        w.write_log_format_version()?;
+        w.write_start_time(1000.0)?;
+        w.write_base_time(1000.0)?;
+        w.write_legend()?;
+        w.write_histogram(&first, 1000.5, 1.0, None)?;
+        w.write_histogram(&second, 1001.5, 1.0, Some("empty"))?;
+        w.write_histogram(&third, 1002.5, 2.0, None)
+    });
+
+    let mut reader = IntervalLogReader::new(Cursor::new(log.as_bytes()));
+    let read_first = reader.next_histogram::<u64>().unwrap().unwrap();
+    assert_eq!(Some(1000.0), reader.start_time());
+    assert_eq!(Some(1000.0), reader.base_time());
+    let read_second = reader.next_histogram::<u64>().unwrap().unwrap();
+    let read_third = reader.next_histogram::<u64>().unwrap().unwrap();
+    assert!(reader.next_histogram::<u64>().unwrap().is_none());
+
+    assert_eq!(None, read_first.tag());
+    assert_eq!(1000.5, read_first.start_timestamp());
+    assert_eq!(1.0, read_first.interval_length());
+    assert_eq!(first.get_count(), read_first.histogram().get_count());
+    assert_eq!(1, read_first.histogram().get_count_at_value(3_000_000).unwrap());
+
+    assert_eq!(Some("empty"), read_second.tag());
+    assert_eq!(0, read_second.histogram().get_count());
+
+    assert_eq!(1002.5, read_third.start_timestamp());
+    assert_eq!(2.0, read_third.interval_length());
+    assert_eq!(7, read_third.into_histogram().get_max());
+}
+
+/// Timestamps in a Java log with a start time and no base time are absolute.
+#[test]
+fn read_absolute_timestamps_without_base_time() {
+    let h = histo_with(&[5]);
+    let log = write_log(|w| {
+        w.write_start_time(1441812279.0)?;
+        w.write_histogram(&h, 1441812280.25, 1.0, None)
+    });
+
+    let mut reader = IntervalLogReader::new(Cursor::new(log.as_bytes()));
+    let interval = reader.next_histogram::<u64>().unwrap().unwrap();
+
+    assert_eq!(Some(0.0), reader.base_time());
+    assert_eq!(1441812280.25, interval.start_timestamp());
+}
+
+/// Timestamps much smaller than the start time with no base time are relative to the start time.
+#[test]
+fn read_relative_timestamps_without_base_time() {
+    let h = histo_with(&[5]);
+    let log = write_log(|w| {
+        w.write_start_time(1441812279.0)?;
+        w.write_histogram(&h, 0.25, 1.0, None)
+    });
+
+    let mut reader = IntervalLogReader::new(Cursor::new(log.as_bytes()));
+    let interval = reader.next_histogram::<u64>().unwrap().unwrap();
+
+    assert_eq!(Some(1441812279.0), reader.base_time());
+    assert_eq!(1441812279.25, interval.start_timestamp());
+}
+
+#[test]
+fn read_java_start_time_with_date() {
+    let h = histo_with(&[5]);
+    let interval = write_log(|w| w.write_histogram(&h, 0.127, 1.0, None));
+    let log = format!("#[StartTime: 1441812279.474 (seconds since epoch), Wed Sep 09 08:24:39 PDT \
+                      2015]\n{}", interval);
+
+    let mut reader = IntervalLogReader::new(Cursor::new(log.as_bytes()));
+    reader.next_histogram::<u64>().unwrap().unwrap();
+
+    assert_eq!(Some(1441812279.474), reader.start_time());
+}
+
+/// A jHiccup log from HdrHistogram's Java test resources, with each interval duplicated under
+/// `Tag=A`. Values are in nanoseconds, and the max column is in milliseconds.
+const JAVA_LOG: &str = include_str!("../../tests/data/tagged-Log.logV2.hlog");
+
+#[test]
+fn read_java_log() {
+    let intervals = read_all(JAVA_LOG, &IntervalLogFilter::new());
+
+    assert_eq!(42, intervals.len());
+    assert_eq!(21, intervals.iter().filter(|i| i.tag().is_none()).count());
+    let first = &intervals[0];
+    assert_eq!(None, first.tag());
+    assert_eq!(1441812279.474 + 0.127, first.start_timestamp());
+    assert_eq!(1.007, first.interval_length());
+    assert_eq!(741, first.histogram().get_count());
+    assert_eq!(2_768_895, first.histogram().get_max());
+
+    let tagged = &intervals[1];
+    assert_eq!(Some("A"), tagged.tag());
+    assert_eq!(first.start_timestamp(), tagged.start_timestamp());
+    assert_eq!(first.histogram().get_count(), tagged.histogram().get_count());
+
+    // 9.132,1.751,1551.892
+    let slow = &intervals[18];
+    assert_eq!(1441812279.474 + 9.132, slow.start_timestamp());
+    assert_eq!(1551.892, (slow.histogram().get_max() as f64 / 1e6 * 1000.0).round() / 1000.0);
+}
+
+#[test]
+fn filter_java_log() {
+    let tagged = read_all(JAVA_LOG, &IntervalLogFilter::new().tag(Some("A")));
+    let untagged = read_all(JAVA_LOG, &IntervalLogFilter::new().tag(None));
+    let later = read_all(JAVA_LOG, &IntervalLogFilter::new().range(0.5, 10.0));
+
+    assert_eq!(21, tagged.len());
+    assert!(tagged.iter().all(|i| i.tag() == Some("A")));
+    assert_eq!(21, untagged.len());
+    // 1.134 to 9.132, tagged and untagged
+    assert_eq!(18, later.len());
+    assert_eq!(1441812279.474 + 1.134, later[0].start_timestamp());
+}
+
+#[test]
+fn write_matches_java_log_columns() {
+    let first = read_all(JAVA_LOG, &IntervalLogFilter::new()).remove(0);
+    let log = write_log(|w| {
+        w.set_max_value_unit_ratio(1e6);
+        w.write_base_time(1441812279.474)?;
+        w.write_histogram(first.histogram(), first.start_timestamp(), first.interval_length(),
+                          None)
+    });
+
+    let java_line = JAVA_LOG.lines().nth(4).unwrap();
+    let line = log.lines().nth(1).unwrap();
+    assert_eq!(&java_line[..java_line.find("HISTF").unwrap()],
+               &line[..line.find("HISTF").unwrap()]);
+}
+
+#[test]
+fn filter_by_relative_range() {
+    let h = histo_with(&[5]);
+    let log = write_log(|w| {
+        w.write_start_time(100.0)?;
+        w.write_base_time(100.0)?;
+        for i in 0..10 {
+            w.write_histogram(&h, 100.0 + i as f64, 1.0, None)?;
+        }
+        Ok(())
+    });
+
+    let intervals = read_all(&log, &IntervalLogFilter::new().range(2.0, 4.0));
+
+    let starts: Vec<f64> = intervals.iter().map(|i| i.start_timestamp()).collect();
+    assert_eq!(vec![102.0, 103.0, 104.0], starts);
+}
+
+#[test]
+fn filter_by_absolute_range() {
+    let h = histo_with(&[5]);
+    let log = write_log(|w| {
+        w.write_start_time(100.0)?;
+        w.write_base_time(100.0)?;
+        for i in 0..10 {
+            w.write_histogram(&h, 100.0 + i as f64, 1.0, None)?;
+        }
+        Ok(())
+    });
+
+    let intervals = read_all(&log, &IntervalLogFilter::new().absolute_range(107.5, 200.0));
+
+    let starts: Vec<f64> = intervals.iter().map(|i| i.start_timestamp()).collect();
+    assert_eq!(vec![108.0, 109.0], starts);
+}
+
+#[test]
+fn filter_by_tag() {
+    let h = histo_with(&[5]);
+    let log = write_log(|w| {
+        w.write_histogram(&h, 1.0, 1.0, None)?;
+        w.write_histogram(&h, 2.0, 1.0, Some("a"))?;
+        w.write_histogram(&h, 3.0, 1.0, Some("b"))?;
+        w.write_histogram(&h, 4.0, 1.0, Some("a"))
+    });
+
+    let tagged = read_all(&log, &IntervalLogFilter::new().tag(Some("a")));
+    let untagged = read_all(&log, &IntervalLogFilter::new().tag(None));
+    let all = read_all(&log, &IntervalLogFilter::new());
+
+    let starts: Vec<f64> = tagged.iter().map(|i| i.start_timestamp()).collect();
+    assert_eq!(vec![2.0, 4.0], starts);
+    assert_eq!(1, untagged.len());
+    assert_eq!(1.0, untagged[0].start_timestamp());
+    assert_eq!(4, all.len());
+}
+
+#[test]
+fn read_malformed_lines() {
+    for line in &["1.0,2.0,3.0", "x,1.0,1.0,HISTF", "Tag=a", "1.0,1.0,1.0,HISTF,extra",
+                  "#[StartTime: soon]"] {
+        let mut reader = IntervalLogReader::new(Cursor::new(line.as_bytes()));
+        match reader.next_histogram::<u64>() {
+            Err(IntervalLogReadError::MalformedLine) => {},
+            other => panic!("line {:?}: {:?}", line, other)
+        }
+    }
+}
+
+#[test]
+fn read_invalid_base64() {
+    let mut reader = IntervalLogReader::new(Cursor::new("1.0,1.0,1.0,not*base64".as_bytes()));
+
+    match reader.next_histogram::<u64>() {
+        Err(IntervalLogReadError::InvalidBase64) => {},
+        other => panic!("{:?}", other)
+    }
+}
+
+#[test]
+fn read_invalid_histogram() {
+    let mut reader = IntervalLogReader::new(Cursor::new("1.0,1.0,1.0,AAAAAAAA".as_bytes()));
+
+    match reader.next_histogram::<u64>() {
+        Err(IntervalLogReadError::Decode(DecodeError::InvalidCookie)) => {},
+        other => panic!("{:?}", other)
+    }
+}
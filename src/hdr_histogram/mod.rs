@@ -1 +1,5 @@
+pub mod errors;
+pub mod interval_log;
 pub mod simple_hdr_histogram;
+
+#[cfg(test)] mod interval_log_test;
@@ -0,0 +1,284 @@
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+use hdr_histogram::simple_hdr_histogram::*;
+
+/// A histogram that many threads can record into at once through `&self`.
+///
+/// Counts are `AtomicU64`s updated with relaxed ordering, so recording never blocks. Reads don't
+/// stop concurrent recording, so while other threads are recording, a read may reflect some of
+/// their recordings but not others. Each read is still internally consistent enough to be useful,
+/// and once recording has stopped (and been synchronized with, e.g. by joining the recording
+/// threads) reads are exact.
+///
+/// Unlike `SimpleHdrHistogram`, this can't auto-resize, since that would need exclusive access.
+#[derive(Debug)]
+pub struct AtomicHistogram {
+    /// Provides the configuration and index math. It has no counts array of its own.
+    layout: SimpleHdrHistogram<u8, LayoutCounts>,
+    counts: Vec<AtomicU64>,
+    total_count: AtomicU64,
+    max_value: AtomicU64,
+    min_non_zero_value: AtomicU64,
+    out_of_range_count: AtomicU64,
+}
+
+impl AtomicHistogram {
+    /// lowest_discernible_value: must be >= 1
+    /// highest_trackable_value: must be >= 2 * lowest_discernible_value
+    /// num_significant_digits: must be <= 5
+    ///
+    /// Panics if the configuration is invalid. Use `try_new` to handle that instead.
+    pub fn new(lowest_discernible_value: u64, highest_trackable_value: u64,
+               num_significant_digits: u32) -> AtomicHistogram {
+        match AtomicHistogram::try_new(lowest_discernible_value, highest_trackable_value,
+                                       num_significant_digits) {
+            Ok(h) => h,
+            Err(e) => panic!("Invalid histogram configuration: {}", e)
+        }
+    }
+
+    /// Like `new`, but returns an error rather than panicking on an invalid configuration.
+    pub fn try_new(lowest_discernible_value: u64, highest_trackable_value: u64,
+                   num_significant_digits: u32) -> Result<AtomicHistogram, CreationError> {
+        let layout = SimpleHdrHistogram::try_new_with_counts(lowest_discernible_value,
+                                                             highest_trackable_value,
+                                                             num_significant_digits)?;
+        Ok(AtomicHistogram::from_layout(layout))
+    }
+
+    /// Create an empty atomic histogram with the same configuration and out of range policy as
+    /// `source`.
+    pub fn new_from<T: HistogramCount, C: Counts<T>>(source: &SimpleHdrHistogram<T, C>)
+            -> AtomicHistogram {
+        let mut layout = SimpleHdrHistogram::new_with_counts(source.lowest_discernible_value,
+                                                             source.highest_trackable_value,
+                                                             source.num_significant_digits);
+        layout.out_of_range_policy = source.out_of_range_policy;
+        AtomicHistogram::from_layout(layout)
+    }
+
+    fn from_layout(layout: SimpleHdrHistogram<u8, LayoutCounts>) -> AtomicHistogram {
+        let counts = (0..layout.counts.len()).map(|_| AtomicU64::new(0)).collect();
+        AtomicHistogram {
+            layout: layout,
+            counts: counts,
+            total_count: AtomicU64::new(0),
+            max_value: AtomicU64::new(0),
+            min_non_zero_value: AtomicU64::new(u64::max_value()),
+            out_of_range_count: AtomicU64::new(0),
+        }
+    }
+
+    /// Set what to do with values beyond the highest trackable value.
+    pub fn set_out_of_range_policy(&mut self, policy: OutOfRangePolicy) {
+        self.layout.out_of_range_policy = policy;
+    }
+
+    pub fn get_out_of_range_policy(&self) -> OutOfRangePolicy {
+        self.layout.out_of_range_policy
+    }
+
+    pub fn get_highest_trackable_value(&self) -> u64 {
+        self.layout.highest_trackable_value
+    }
+
+    pub fn record_single_value(&self, value: u64) -> Result<(), RecordError> {
+        self.record_value_with_count(value, 1)
+    }
+
+    pub fn record_value_with_count(&self, value: u64, count: u64) -> Result<(), RecordError> {
+        let mut value = value;
+        let mut counts_index = self.layout.counts_array_index(value);
+        if counts_index >= self.counts.len() {
+            match self.layout.out_of_range_policy {
+                OutOfRangePolicy::Error => return Err(RecordError::ValueOutOfRange),
+                OutOfRangePolicy::Clamp => {
+                    counts_index = self.counts.len() - 1;
+                    value = self.layout.highest_equivalent_value(
+                        self.layout.value_from_index(counts_index));
+                }
+                OutOfRangePolicy::Drop => {
+                    add_checked(&self.out_of_range_count, count)?;
+                    return Ok(());
+                }
+            }
+        }
+
+        add_checked(&self.counts[counts_index], count)?;
+        if let Err(e) = add_checked(&self.total_count, count) {
+            self.counts[counts_index].fetch_sub(count, Ordering::Relaxed);
+            return Err(e);
+        }
+        // same rounding as SimpleHdrHistogram::update_min_and_max
+        self.max_value.fetch_max(value | self.layout.unit_magnitude_mask, Ordering::Relaxed);
+        if value > self.layout.unit_magnitude_mask {
+            self.min_non_zero_value.fetch_min(value & !self.layout.unit_magnitude_mask,
+                                              Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    pub fn get_count(&self) -> u64 {
+        self.total_count.load(Ordering::Relaxed)
+    }
+
+    pub fn get_out_of_range_count(&self) -> u64 {
+        self.out_of_range_count.load(Ordering::Relaxed)
+    }
+
+    pub fn get_count_at_value(&self, value: u64) -> Result<u64, QueryError> {
+        let index = self.layout.counts_array_index(value);
+        match self.counts.get(index) {
+            Some(c) => Ok(c.load(Ordering::Relaxed)),
+            None => Err(QueryError::IndexOutOfRange)
+        }
+    }
+
+    pub fn get_max(&self) -> u64 {
+        self.max_value.load(Ordering::Relaxed)
+    }
+
+    pub fn get_min_non_zero(&self) -> u64 {
+        self.min_non_zero_value.load(Ordering::Relaxed)
+    }
+
+    /// See `HistogramBase::get_value_at_percentile`.
+    ///
+    /// The counts are read only once. If they don't add up to the total count that was read first
+    /// (because recording is ongoing), the highest non-zero count's value is used.
+    pub fn get_value_at_percentile(&self, percentile: f64) -> u64 {
+        let requested_percentile = percentile.min(100.0);
+        let count_at_percentile = cmp::max(
+            (((requested_percentile / 100.0) * self.get_count() as f64) + 0.5) as u64, 1);
+
+        let mut total_to_current_index: u64 = 0;
+        let mut last_non_zero_index = None;
+        for (i, c) in self.counts.iter().enumerate() {
+            let count = c.load(Ordering::Relaxed);
+            if count == 0 {
+                continue;
+            }
+            last_non_zero_index = Some(i);
+            total_to_current_index = total_to_current_index.saturating_add(count);
+            if total_to_current_index >= count_at_percentile {
+                break;
+            }
+        }
+
+        match last_non_zero_index {
+            Some(i) => {
+                let value_at_index = self.layout.value_from_index(i);
+                if percentile == 0.0 {
+                    self.layout.lowest_equivalent_value(value_at_index)
+                } else {
+                    self.layout.highest_equivalent_value(value_at_index)
+                }
+            }
+            None => 0
+        }
+    }
+
+    /// Clear all recorded values.
+    ///
+    /// Values recorded by other threads while this is in progress may be partly kept and partly
+    /// cleared. `Recorder` avoids that by only resetting histograms nothing is recording into.
+    pub fn reset(&self) {
+        for c in self.counts.iter() {
+            c.store(0, Ordering::Relaxed);
+        }
+        self.total_count.store(0, Ordering::Relaxed);
+        self.max_value.store(0, Ordering::Relaxed);
+        self.min_non_zero_value.store(u64::max_value(), Ordering::Relaxed);
+        self.out_of_range_count.store(0, Ordering::Relaxed);
+    }
+
+    /// Copy the current state into a new `SimpleHdrHistogram` with the same configuration and
+    /// out of range policy, for analysis with the full single-threaded API.
+    ///
+    /// The snapshot's total count is the sum of the counts it copied, so it's consistent even if
+    /// other threads are recording.
+    pub fn snapshot(&self) -> SimpleHdrHistogram<u64> {
+        let mut h = SimpleHdrHistogram::new(self.layout.lowest_discernible_value,
+                                            self.layout.highest_trackable_value,
+                                            self.layout.num_significant_digits);
+        self.snapshot_counts_into(&mut h);
+        h
+    }
+
+    /// Like `snapshot`, but replaces the contents of `target` instead of allocating a new
+    /// histogram. `target`'s counts array is reused if it has the same configuration as this
+    /// histogram (e.g. if it came from an earlier `snapshot`); otherwise it's replaced.
+    pub fn snapshot_into(&self, target: &mut SimpleHdrHistogram<u64>) {
+        if target.lowest_discernible_value != self.layout.lowest_discernible_value
+                || target.highest_trackable_value != self.layout.highest_trackable_value
+                || target.num_significant_digits != self.layout.num_significant_digits
+                || target.counts.len() != self.counts.len() {
+            *target = self.snapshot();
+            return;
+        }
+        self.snapshot_counts_into(target);
+    }
+
+    /// Overwrite everything but the configuration of `h`, which must have the same layout.
+    fn snapshot_counts_into(&self, h: &mut SimpleHdrHistogram<u64>) {
+        h.reset();
+        let mut total_count = 0_u64;
+        for (dest, c) in h.counts.iter_mut().zip(self.counts.iter()) {
+            *dest = c.load(Ordering::Relaxed);
+            // a concurrent recording that overflowed may not have been undone yet
+            total_count = total_count.saturating_add(*dest);
+        }
+        h.total_count = total_count as u128;
+        if total_count > 0 {
+            h.max_value = self.get_max();
+            h.min_non_zero_value = self.get_min_non_zero();
+        }
+        h.out_of_range_count = self.get_out_of_range_count() as u128;
+        h.auto_resize = false;
+        h.out_of_range_policy = self.layout.out_of_range_policy;
+    }
+}
+
+/// Counts storage for `AtomicHistogram`'s layout, which only needs to know how long the counts
+/// array is. Every count reads as zero, and nothing ever records into a layout.
+#[derive(Debug, Clone)]
+struct LayoutCounts {
+    len: usize,
+}
+
+impl Counts<u8> for LayoutCounts {
+    fn with_len(len: usize) -> LayoutCounts {
+        LayoutCounts {
+            len: len
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn get(&self, _index: usize) -> u8 {
+        0
+    }
+
+    fn set(&mut self, _index: usize, _count: u8) {
+        unreachable!("AtomicHistogram layouts have no counts to set");
+    }
+
+    fn grow(&mut self, new_len: usize) {
+        self.len = new_len;
+    }
+
+    fn reset(&mut self) {}
+}
+
+/// Add `count` to `atomic`, undoing the addition if it overflowed.
+fn add_checked(atomic: &AtomicU64, count: u64) -> Result<(), RecordError> {
+    let previous = atomic.fetch_add(count, Ordering::Relaxed);
+    if previous.checked_add(count).is_none() {
+        atomic.fetch_sub(count, Ordering::Relaxed);
+        return Err(RecordError::CountOverflow);
+    }
+    Ok(())
+}
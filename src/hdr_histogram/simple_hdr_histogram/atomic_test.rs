@@ -0,0 +1,207 @@
+use std::sync::Arc;
+use std::thread;
+
+use hdr_histogram::simple_hdr_histogram::*;
+
+#[test]
+fn new_matches_simple_layout() {
+    let h = AtomicHistogram::new(1, 3600 * 1000 * 1000, 3);
+    let simple = SimpleHdrHistogram::<u64>::new(1, 3600 * 1000 * 1000, 3);
+
+    assert_eq!(simple.counts.len(), h.snapshot().counts.len());
+    assert_eq!(0, h.get_count());
+    assert_eq!(0, h.get_max());
+    assert_eq!(u64::max_value(), h.get_min_non_zero());
+}
+
+#[test]
+fn try_new_invalid() {
+    assert_eq!(CreationError::LowIsZero, AtomicHistogram::try_new(0, 100, 3).unwrap_err());
+}
+
+#[test]
+#[should_panic(expected = "Invalid histogram configuration")]
+fn new_invalid_panics() {
+    AtomicHistogram::new(1, 100, 6);
+}
+
+#[test]
+fn record_matches_simple() {
+    let h = AtomicHistogram::new(1024, 1 << 40, 2);
+    let mut simple = SimpleHdrHistogram::<u64>::new(1024, 1 << 40, 2);
+    for &(v, c) in &[(0, 1), (5000, 2), (100_000, 3), (1 << 30, 4), (2047, 5)] {
+        h.record_value_with_count(v, c).unwrap();
+        simple.record_value_with_count(v, c).unwrap();
+    }
+
+    assert_eq!(simple.get_count(), h.get_count() as u128);
+    assert_eq!(simple.get_max(), h.get_max());
+    assert_eq!(simple.get_min_non_zero(), h.get_min_non_zero());
+    assert_eq!(simple.get_count_at_value(5000).unwrap(), h.get_count_at_value(5000).unwrap());
+    for &p in &[0.0, 10.0, 50.0, 73.0, 99.9, 100.0] {
+        assert_eq!(simple.get_value_at_percentile(p), h.get_value_at_percentile(p), "{}", p);
+    }
+
+    let snapshot = h.snapshot();
+    assert_eq!(simple.counts, snapshot.counts);
+    assert_eq!(simple.get_count(), snapshot.get_count());
+    assert_eq!(simple.get_max(), snapshot.get_max());
+    assert_eq!(simple.get_min_non_zero(), snapshot.get_min_non_zero());
+    assert_eq!(simple.highest_trackable_value, snapshot.highest_trackable_value);
+}
+
+#[test]
+fn get_value_at_percentile_empty() {
+    let h = AtomicHistogram::new(1, 100_000, 3);
+
+    assert_eq!(0, h.get_value_at_percentile(50.0));
+}
+
+#[test]
+fn get_count_at_value_out_of_range() {
+    let h = AtomicHistogram::new(1, 1000, 3);
+
+    assert_eq!(QueryError::IndexOutOfRange, h.get_count_at_value(1 << 20).unwrap_err());
+}
+
+#[test]
+fn record_out_of_range_policies() {
+    let mut h = AtomicHistogram::new(1, 1000, 3);
+    assert_eq!(OutOfRangePolicy::Error, h.get_out_of_range_policy());
+    assert_eq!(Err(RecordError::ValueOutOfRange), h.record_single_value(1 << 20));
+
+    h.set_out_of_range_policy(OutOfRangePolicy::Drop);
+    h.record_value_with_count(1 << 20, 3).unwrap();
+    assert_eq!(0, h.get_count());
+    assert_eq!(3, h.get_out_of_range_count());
+
+    h.set_out_of_range_policy(OutOfRangePolicy::Clamp);
+    h.record_single_value(1 << 20).unwrap();
+    // the top of the counts array is beyond the highest trackable value
+    assert_eq!(1000, h.get_highest_trackable_value());
+    assert_eq!(2047, h.get_max());
+    assert_eq!(1, h.get_count_at_value(2047).unwrap());
+
+    let snapshot = h.snapshot();
+    assert_eq!(3, snapshot.get_out_of_range_count());
+    assert_eq!(OutOfRangePolicy::Clamp, snapshot.get_out_of_range_policy());
+}
+
+#[test]
+fn record_count_overflow_is_undone() {
+    let h = AtomicHistogram::new(1, 1000, 3);
+    h.record_value_with_count(10, u64::max_value()).unwrap();
+
+    assert_eq!(Err(RecordError::CountOverflow), h.record_single_value(10));
+    assert_eq!(Err(RecordError::CountOverflow), h.record_single_value(20));
+
+    assert_eq!(u64::max_value(), h.get_count_at_value(10).unwrap());
+    assert_eq!(0, h.get_count_at_value(20).unwrap());
+    assert_eq!(u64::max_value(), h.get_count());
+    assert_eq!(10, h.get_max());
+}
+
+#[test]
+fn new_from_simple() {
+    let mut simple = SimpleHdrHistogram::<u8>::new(1, 1000, 2);
+    simple.set_out_of_range_policy(OutOfRangePolicy::Drop);
+
+    let h = AtomicHistogram::new_from(&simple);
+
+    assert_eq!(simple.counts.len(), h.snapshot().counts.len());
+    assert_eq!(OutOfRangePolicy::Drop, h.get_out_of_range_policy());
+}
+
+#[test]
+fn record_from_many_threads() {
+    let h = Arc::new(AtomicHistogram::new(1, 3600 * 1000 * 1000, 3));
+
+    let threads: Vec<_> = (0..8).map(|t| {
+        let h = h.clone();
+        thread::spawn(move || {
+            for i in 0..10_000 {
+                h.record_single_value(t * 100_000 + i).unwrap();
+            }
+        })
+    }).collect();
+    for t in threads {
+        t.join().unwrap();
+    }
+
+    let mut expected = SimpleHdrHistogram::<u64>::new(1, 3600 * 1000 * 1000, 3);
+    for t in 0..8 {
+        for i in 0..10_000 {
+            expected.record_single_value(t * 100_000 + i).unwrap();
+        }
+    }
+    let snapshot = h.snapshot();
+    assert_eq!(80_000, h.get_count());
+    assert_eq!(expected.counts, snapshot.counts);
+    assert_eq!(expected.get_max(), h.get_max());
+    assert_eq!(expected.get_min_non_zero(), h.get_min_non_zero());
+    assert_eq!(expected.get_value_at_percentile(99.0), h.get_value_at_percentile(99.0));
+}
+
+#[test]
+fn reset_clears_everything() {
+    let mut h = AtomicHistogram::new(1, 1000, 3);
+    h.set_out_of_range_policy(OutOfRangePolicy::Drop);
+    h.record_value_with_count(10, 3).unwrap();
+    h.record_single_value(1 << 20).unwrap();
+
+    h.reset();
+
+    assert_eq!(0, h.get_count());
+    assert_eq!(0, h.get_count_at_value(10).unwrap());
+    assert_eq!(0, h.get_max());
+    assert_eq!(u64::max_value(), h.get_min_non_zero());
+    assert_eq!(0, h.get_out_of_range_count());
+    assert_eq!(OutOfRangePolicy::Drop, h.get_out_of_range_policy());
+}
+
+#[test]
+fn snapshot_into_reuses_matching_counts() {
+    let h = AtomicHistogram::new(1, 100_000, 3);
+    h.record_value_with_count(10, 3).unwrap();
+    let mut target = h.snapshot();
+    let counts_ptr = target.counts.as_ptr();
+    h.reset();
+    h.record_single_value(500).unwrap();
+
+    h.snapshot_into(&mut target);
+
+    assert_eq!(counts_ptr, target.counts.as_ptr());
+    assert_eq!(1, target.get_count());
+    assert_eq!(0, target.get_count_at_value(10).unwrap());
+    assert_eq!(1, target.get_count_at_value(500).unwrap());
+    assert_eq!(500, target.get_max());
+    assert_eq!(500, target.get_min_non_zero());
+}
+
+#[test]
+fn snapshot_into_replaces_other_layout() {
+    let h = AtomicHistogram::new(1, 100_000, 3);
+    h.record_single_value(50_000).unwrap();
+    let mut target = SimpleHdrHistogram::<u64>::new(1, 1000, 2);
+    target.record_single_value(5).unwrap();
+
+    h.snapshot_into(&mut target);
+
+    assert_eq!(h.snapshot().counts, target.counts);
+    assert_eq!(100_000, target.get_highest_trackable_value());
+    assert_eq!(1, target.get_count());
+}
+
+#[test]
+fn snapshot_empty() {
+    let h = AtomicHistogram::new(1, 100_000, 3);
+    h.record_single_value(5).unwrap();
+    let mut target = h.snapshot();
+    h.reset();
+
+    h.snapshot_into(&mut target);
+
+    assert_eq!(0, target.get_count());
+    assert_eq!(0, target.get_max());
+    assert_eq!(u64::max_value(), target.get_min_non_zero());
+}
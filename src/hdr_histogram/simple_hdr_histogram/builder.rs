@@ -0,0 +1,98 @@
+use hdr_histogram::simple_hdr_histogram::*;
+
+/// Collects histogram configuration in one place so that it can be validated all at once when
+/// the histogram is built. Useful when configuration comes from somewhere untrusted, like a
+/// config file.
+///
+/// Defaults to a lowest discernible value of 1, `OutOfRangePolicy::Error`,
+/// `CountOverflowPolicy::Error` and a value unit ratio of 1.0. As with Java's single argument
+/// `Histogram` constructor, if no highest trackable value is set, the histogram starts out
+/// covering 2 * the lowest discernible value and auto-resizes as needed; otherwise auto-resizing
+/// is off unless enabled.
+#[derive(Debug, Clone)]
+pub struct HistogramBuilder {
+    lowest_discernible_value: u64,
+    highest_trackable_value: Option<u64>,
+    num_significant_digits: u32,
+    auto_resize: Option<bool>,
+    out_of_range_policy: OutOfRangePolicy,
+    count_overflow_policy: CountOverflowPolicy,
+    value_unit_ratio: f64
+}
+
+impl HistogramBuilder {
+    /// num_significant_digits: must be <= 5
+    pub fn new(num_significant_digits: u32) -> HistogramBuilder {
+        HistogramBuilder {
+            lowest_discernible_value: 1,
+            highest_trackable_value: None,
+            num_significant_digits: num_significant_digits,
+            auto_resize: None,
+            out_of_range_policy: OutOfRangePolicy::Error,
+            count_overflow_policy: CountOverflowPolicy::Error,
+            value_unit_ratio: 1.0
+        }
+    }
+
+    /// The smallest value that can be distinguished from 0, which effectively sets the units of
+    /// the recorded values. Must be >= 1.
+    pub fn lowest_discernible_value(mut self, value: u64) -> HistogramBuilder {
+        self.lowest_discernible_value = value;
+        self
+    }
+
+    /// Must be >= 2 * lowest discernible value.
+    pub fn highest_trackable_value(mut self, value: u64) -> HistogramBuilder {
+        self.highest_trackable_value = Some(value);
+        self
+    }
+
+    pub fn num_significant_digits(mut self, digits: u32) -> HistogramBuilder {
+        self.num_significant_digits = digits;
+        self
+    }
+
+    /// Grow the histogram as needed to hold values beyond the highest trackable value. When
+    /// this is enabled, the highest trackable value is just a hint for the initial size.
+    pub fn auto_resize(mut self, enabled: bool) -> HistogramBuilder {
+        self.auto_resize = Some(enabled);
+        self
+    }
+
+    /// What to do with values beyond the highest trackable value when not auto-resizing.
+    pub fn out_of_range_policy(mut self, policy: OutOfRangePolicy) -> HistogramBuilder {
+        self.out_of_range_policy = policy;
+        self
+    }
+
+    /// What to do when the count at a value would overflow the count type.
+    pub fn count_overflow_policy(mut self, policy: CountOverflowPolicy) -> HistogramBuilder {
+        self.count_overflow_policy = policy;
+        self
+    }
+
+    /// How much one recorded unit is worth in the units values are reported in, e.g. 0.001 for
+    /// values recorded in microseconds and reported in milliseconds. See
+    /// `SimpleHdrHistogram::get_value_unit_ratio`. Must be positive and finite.
+    pub fn value_unit_ratio(mut self, ratio: f64) -> HistogramBuilder {
+        self.value_unit_ratio = ratio;
+        self
+    }
+
+    /// Build a histogram with the chosen count type.
+    pub fn build<T: HistogramCount>(&self) -> Result<SimpleHdrHistogram<T>, CreationError> {
+        if !(self.value_unit_ratio > 0.0 && self.value_unit_ratio.is_finite()) {
+            return Err(CreationError::InvalidValueUnitRatio);
+        }
+        let highest_trackable_value = self.highest_trackable_value
+            .unwrap_or_else(|| self.lowest_discernible_value.saturating_mul(2));
+        let mut h = SimpleHdrHistogram::try_new(self.lowest_discernible_value,
+                                                highest_trackable_value,
+                                                self.num_significant_digits)?;
+        h.set_auto_resize(self.auto_resize.unwrap_or(self.highest_trackable_value.is_none()));
+        h.set_out_of_range_policy(self.out_of_range_policy);
+        h.set_count_overflow_policy(self.count_overflow_policy);
+        h.integer_to_double_value_conversion_ratio = self.value_unit_ratio;
+        Ok(h)
+    }
+}
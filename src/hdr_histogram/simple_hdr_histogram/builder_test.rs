@@ -0,0 +1,171 @@
+use hdr_histogram::simple_hdr_histogram::*;
+
+#[test]
+fn build_with_defaults() {
+    let h = HistogramBuilder::new(3).build::<u64>().unwrap();
+
+    assert_eq!(1, h.lowest_discernible_value);
+    assert_eq!(2, h.highest_trackable_value);
+    assert_eq!(3, h.num_significant_digits);
+    assert!(h.is_auto_resize());
+    assert_eq!(OutOfRangePolicy::Error, h.get_out_of_range_policy());
+    assert_eq!(CountOverflowPolicy::Error, h.get_count_overflow_policy());
+    assert_eq!(1.0, h.get_value_unit_ratio());
+}
+
+#[test]
+fn build_with_defaults_records_any_value() {
+    let mut h = HistogramBuilder::new(3).lowest_discernible_value(1000).build::<u64>().unwrap();
+
+    h.record_single_value(1 << 40).unwrap();
+
+    assert_eq!(1, h.get_count_at_value(1 << 40).unwrap());
+}
+
+#[test]
+fn build_with_highest_does_not_auto_resize() {
+    let mut h = HistogramBuilder::new(3).highest_trackable_value(1000).build::<u64>().unwrap();
+
+    assert!(!h.is_auto_resize());
+    assert_eq!(RecordError::ValueOutOfRange, h.record_single_value(1 << 20).unwrap_err());
+}
+
+#[test]
+fn build_without_highest_or_auto_resize() {
+    let mut h = HistogramBuilder::new(3).auto_resize(false).build::<u64>().unwrap();
+
+    assert_eq!(RecordError::ValueOutOfRange, h.record_single_value(1 << 20).unwrap_err());
+}
+
+#[test]
+fn build_value_unit_ratio() {
+    let mut h = HistogramBuilder::new(3)
+        .highest_trackable_value(1_000_000)
+        .value_unit_ratio(0.001)
+        .build::<u64>()
+        .unwrap();
+    h.record_single_value(1500).unwrap();
+
+    assert_eq!(0.001, h.get_value_unit_ratio());
+    let v = h.recorded_values().into_iter().next().unwrap();
+    assert_eq!(1.5, v.double_value_iterated_to());
+    assert_eq!(0.001, SimpleHdrHistogram::<u64>::decode(&h.encode().unwrap()).unwrap()
+        .get_value_unit_ratio());
+    assert_eq!(0.001, SimpleHdrHistogram::new_from(&h).get_value_unit_ratio());
+}
+
+#[test]
+fn build_invalid_value_unit_ratio() {
+    for &ratio in &[0.0, -1.0, f64::NAN, f64::INFINITY] {
+        let result = HistogramBuilder::new(3).value_unit_ratio(ratio).build::<u64>();
+
+        assert_eq!(CreationError::InvalidValueUnitRatio, result.unwrap_err(), "{}", ratio);
+    }
+}
+
+#[test]
+fn build_count_overflow_policy() {
+    let mut h = HistogramBuilder::new(2)
+        .highest_trackable_value(1000)
+        .count_overflow_policy(CountOverflowPolicy::Saturate)
+        .build::<u8>()
+        .unwrap();
+
+    h.record_value_with_count(10, 255).unwrap();
+    h.record_single_value(10).unwrap();
+
+    assert_eq!(1, h.get_saturated_count());
+}
+
+#[test]
+fn build_out_of_range_policy() {
+    let mut h = HistogramBuilder::new(3)
+        .highest_trackable_value(1000)
+        .out_of_range_policy(OutOfRangePolicy::Drop)
+        .build::<u64>()
+        .unwrap();
+
+    h.record_single_value(1 << 20).unwrap();
+
+    assert_eq!(1, h.get_out_of_range_count());
+}
+
+#[test]
+fn build_auto_resize() {
+    let mut h = HistogramBuilder::new(3).auto_resize(true).build::<u64>().unwrap();
+
+    h.record_single_value(u64::max_value()).unwrap();
+
+    assert_eq!(1, h.get_count_at_value(u64::max_value()).unwrap());
+    assert_eq!(u64::max_value(), h.get_highest_trackable_value());
+}
+
+#[test]
+fn build_matches_new() {
+    let built = HistogramBuilder::new(2)
+        .lowest_discernible_value(4)
+        .highest_trackable_value(100_000)
+        .build::<u8>()
+        .unwrap();
+    let constructed = SimpleHdrHistogram::<u8>::new(4, 100_000, 2);
+
+    assert_eq!(constructed.counts.len(), built.counts.len());
+    assert_eq!(constructed.unit_magnitude, built.unit_magnitude);
+    assert_eq!(constructed.sub_bucket_count, built.sub_bucket_count);
+}
+
+#[test]
+fn build_low_is_zero() {
+    let result = HistogramBuilder::new(3)
+        .lowest_discernible_value(0)
+        .highest_trackable_value(100)
+        .build::<u64>();
+
+    assert_eq!(CreationError::LowIsZero, result.unwrap_err());
+}
+
+#[test]
+fn build_high_less_than_twice_low() {
+    let result = HistogramBuilder::new(3)
+        .lowest_discernible_value(10)
+        .highest_trackable_value(19)
+        .build::<u64>();
+
+    assert_eq!(CreationError::HighLessThanTwiceLow, result.unwrap_err());
+}
+
+#[test]
+fn build_twice_low_would_overflow() {
+    let result = HistogramBuilder::new(3)
+        .lowest_discernible_value(u64::max_value() / 2 + 1)
+        .highest_trackable_value(u64::max_value())
+        .build::<u64>();
+
+    assert_eq!(CreationError::HighLessThanTwiceLow, result.unwrap_err());
+}
+
+#[test]
+fn build_sig_fig_exceeds_max() {
+    let result = HistogramBuilder::new(6)
+        .highest_trackable_value(100)
+        .build::<u64>();
+
+    assert_eq!(CreationError::SigFigExceedsMax, result.unwrap_err());
+}
+
+#[test]
+fn build_cannot_represent_sig_fig_beyond_low() {
+    // unit magnitude 55 + sub bucket half count magnitude 10 > 61
+    let result = HistogramBuilder::new(3)
+        .lowest_discernible_value(1 << 55)
+        .highest_trackable_value(u64::max_value())
+        .build::<u64>();
+
+    assert_eq!(CreationError::CannotRepresentSigFigBeyondLow, result.unwrap_err());
+}
+
+#[test]
+#[should_panic]
+fn new_panics_on_invalid_configuration() {
+    SimpleHdrHistogram::<u64>::new(0, 100, 3);
+}
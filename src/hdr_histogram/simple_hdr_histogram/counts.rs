@@ -0,0 +1,236 @@
+use hdr_histogram::simple_hdr_histogram::*;
+
+/// Storage for a histogram's counts array. Indexes are physical indexes into the array; the
+/// histogram takes care of normalizing them.
+///
+/// `Vec<T>` is the default, with one `T` per slot. Other implementations trade some speed for
+/// memory.
+pub trait Counts<T: HistogramCount> : Clone {
+    /// An array of `len` zero counts.
+    fn with_len(len: usize) -> Self;
+    fn len(&self) -> usize;
+    /// Always false, since every histogram has at least one bucket.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// index: must be < len
+    fn get(&self, index: usize) -> T;
+    /// index: must be < len
+    fn set(&mut self, index: usize, count: T);
+    /// Add zero counts at the end so that the array has `new_len` counts.
+    fn grow(&mut self, new_len: usize);
+    /// Set every count to zero without changing the length.
+    fn reset(&mut self);
+}
+
+impl<T: HistogramCount> Counts<T> for Vec<T> {
+    fn with_len(len: usize) -> Vec<T> {
+        vec![T::zero(); len]
+    }
+
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn get(&self, index: usize) -> T {
+        self[index]
+    }
+
+    fn set(&mut self, index: usize, count: T) {
+        self[index] = count;
+    }
+
+    fn grow(&mut self, new_len: usize) {
+        Vec::resize(self, new_len, T::zero());
+    }
+
+    fn reset(&mut self) {
+        for count in self.iter_mut() {
+            *count = T::zero();
+        }
+    }
+}
+
+/// A histogram with u64 counts that only uses as many bytes per count as its largest count needs.
+pub type AutoWideningHistogram = SimpleHdrHistogram<u64, AutoWideningCounts>;
+
+/// u64 counts stored as u8 until some count doesn't fit, at which point every count is copied
+/// into a u16 array, and so on through u32 and u64. Since most histograms only have large counts
+/// in a few buckets, if any, this usually saves a lot of memory compared to `Vec<u64>` at the cost
+/// of a copy each time the array widens.
+///
+/// Counts never narrow again, even after a reset.
+#[derive(Debug, Clone)]
+pub struct AutoWideningCounts {
+    counts: WidthCounts,
+}
+
+#[derive(Debug, Clone)]
+enum WidthCounts {
+    U8(Vec<u8>),
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+    U64(Vec<u64>),
+}
+
+impl AutoWideningCounts {
+    /// The number of bytes currently used for each count.
+    pub fn bytes_per_count(&self) -> usize {
+        match self.counts {
+            WidthCounts::U8(_) => 1,
+            WidthCounts::U16(_) => 2,
+            WidthCounts::U32(_) => 4,
+            WidthCounts::U64(_) => 8,
+        }
+    }
+
+    fn max_count(&self) -> u64 {
+        match self.counts {
+            WidthCounts::U8(_) => u8::max_value() as u64,
+            WidthCounts::U16(_) => u16::max_value() as u64,
+            WidthCounts::U32(_) => u32::max_value() as u64,
+            WidthCounts::U64(_) => u64::max_value(),
+        }
+    }
+
+    /// Copy the counts into an array of the next wider type.
+    fn widen(&mut self) {
+        let wider = match self.counts {
+            WidthCounts::U8(ref c) => WidthCounts::U16(c.iter().map(|&x| x as u16).collect()),
+            WidthCounts::U16(ref c) => WidthCounts::U32(c.iter().map(|&x| x as u32).collect()),
+            WidthCounts::U32(ref c) => WidthCounts::U64(c.iter().map(|&x| x as u64).collect()),
+            // any u64 count fits already
+            WidthCounts::U64(_) => return,
+        };
+        self.counts = wider;
+    }
+}
+
+impl Counts<u64> for AutoWideningCounts {
+    fn with_len(len: usize) -> AutoWideningCounts {
+        AutoWideningCounts {
+            counts: WidthCounts::U8(vec![0; len])
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self.counts {
+            WidthCounts::U8(ref c) => c.len(),
+            WidthCounts::U16(ref c) => c.len(),
+            WidthCounts::U32(ref c) => c.len(),
+            WidthCounts::U64(ref c) => c.len(),
+        }
+    }
+
+    fn get(&self, index: usize) -> u64 {
+        match self.counts {
+            WidthCounts::U8(ref c) => c[index] as u64,
+            WidthCounts::U16(ref c) => c[index] as u64,
+            WidthCounts::U32(ref c) => c[index] as u64,
+            WidthCounts::U64(ref c) => c[index],
+        }
+    }
+
+    fn set(&mut self, index: usize, count: u64) {
+        while count > self.max_count() {
+            self.widen();
+        }
+        // count fits in the current type, so the casts don't truncate
+        match self.counts {
+            WidthCounts::U8(ref mut c) => c[index] = count as u8,
+            WidthCounts::U16(ref mut c) => c[index] = count as u16,
+            WidthCounts::U32(ref mut c) => c[index] = count as u32,
+            WidthCounts::U64(ref mut c) => c[index] = count,
+        }
+    }
+
+    fn grow(&mut self, new_len: usize) {
+        match self.counts {
+            WidthCounts::U8(ref mut c) => c.resize(new_len, 0),
+            WidthCounts::U16(ref mut c) => c.resize(new_len, 0),
+            WidthCounts::U32(ref mut c) => c.resize(new_len, 0),
+            WidthCounts::U64(ref mut c) => c.resize(new_len, 0),
+        }
+    }
+
+    fn reset(&mut self) {
+        match self.counts {
+            WidthCounts::U8(ref mut c) => for x in c.iter_mut() { *x = 0 },
+            WidthCounts::U16(ref mut c) => for x in c.iter_mut() { *x = 0 },
+            WidthCounts::U32(ref mut c) => for x in c.iter_mut() { *x = 0 },
+            WidthCounts::U64(ref mut c) => for x in c.iter_mut() { *x = 0 },
+        }
+    }
+}
+
+/// Number of counts in each `PackedCounts` page.
+const PACKED_COUNTS_PAGE_LEN: usize = 256;
+
+/// A histogram whose counts array only takes memory where counts are non-zero.
+pub type PackedHistogram<T> = SimpleHdrHistogram<T, PackedCounts<T>>;
+
+/// Counts split into pages that are only allocated once some count in them is non-zero, as in
+/// the Java `PackedHistogram`.
+///
+/// Recorded values tend to cluster in a small part of a histogram's range, so with high precision
+/// or a wide range, most of a `Vec`'s counts would be zero. A packed histogram instead costs a
+/// pointer per 256 counts plus the pages in use, at the cost of an extra indirection per access.
+#[derive(Debug, Clone)]
+pub struct PackedCounts<T> {
+    len: usize,
+    /// `None` for pages whose counts are all zero
+    pages: Vec<Option<Box<[T]>>>,
+}
+
+impl<T: HistogramCount> PackedCounts<T> {
+    /// The number of counts that have memory allocated for them.
+    pub fn allocated_len(&self) -> usize {
+        self.pages.iter().filter(|p| p.is_some()).count() * PACKED_COUNTS_PAGE_LEN
+    }
+
+    fn pages_needed(len: usize) -> usize {
+        len.div_ceil(PACKED_COUNTS_PAGE_LEN)
+    }
+}
+
+impl<T: HistogramCount> Counts<T> for PackedCounts<T> {
+    fn with_len(len: usize) -> PackedCounts<T> {
+        PackedCounts {
+            len: len,
+            pages: vec![None; PackedCounts::<T>::pages_needed(len)]
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn get(&self, index: usize) -> T {
+        match self.pages[index / PACKED_COUNTS_PAGE_LEN] {
+            Some(ref page) => page[index % PACKED_COUNTS_PAGE_LEN],
+            None => T::zero()
+        }
+    }
+
+    fn set(&mut self, index: usize, count: T) {
+        let page_index = index / PACKED_COUNTS_PAGE_LEN;
+        if count == T::zero() && self.pages[page_index].is_none() {
+            return;
+        }
+        let page = self.pages[page_index].get_or_insert_with(
+            || vec![T::zero(); PACKED_COUNTS_PAGE_LEN].into_boxed_slice());
+        page[index % PACKED_COUNTS_PAGE_LEN] = count;
+    }
+
+    fn grow(&mut self, new_len: usize) {
+        self.len = new_len;
+        self.pages.resize(PackedCounts::<T>::pages_needed(new_len), None);
+    }
+
+    /// Frees every page, since they're all zero.
+    fn reset(&mut self) {
+        for page in self.pages.iter_mut() {
+            *page = None;
+        }
+    }
+}
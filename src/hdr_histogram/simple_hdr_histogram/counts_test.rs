@@ -0,0 +1,210 @@
+use hdr_histogram::simple_hdr_histogram::*;
+
+fn widening(lowest_discernible_value: u64, highest_trackable_value: u64,
+            num_significant_digits: u32) -> AutoWideningHistogram {
+    AutoWideningHistogram::new_with_counts(lowest_discernible_value, highest_trackable_value,
+                                           num_significant_digits)
+}
+
+#[test]
+fn vec_counts() {
+    let mut counts: Vec<u16> = Counts::with_len(4);
+    counts.set(2, 7);
+    counts.grow(6);
+
+    assert_eq!(vec![0, 0, 7, 0, 0, 0], counts);
+    counts.reset();
+    assert_eq!(vec![0; 6], counts);
+}
+
+#[test]
+fn widening_counts_widen_as_needed() {
+    let mut counts = AutoWideningCounts::with_len(4);
+    assert_eq!(1, counts.bytes_per_count());
+
+    counts.set(1, 255);
+    assert_eq!(1, counts.bytes_per_count());
+    counts.set(2, 256);
+    assert_eq!(2, counts.bytes_per_count());
+    counts.set(3, 1 << 40);
+    assert_eq!(8, counts.bytes_per_count());
+
+    assert_eq!(vec![0, 255, 256, 1 << 40], (0..4).map(|i| counts.get(i)).collect::<Vec<u64>>());
+}
+
+#[test]
+fn widening_counts_grow_and_reset_keep_width() {
+    let mut counts = AutoWideningCounts::with_len(2);
+    counts.set(0, 70_000);
+    counts.grow(3);
+
+    assert_eq!(3, counts.len());
+    assert_eq!(70_000, counts.get(0));
+    assert_eq!(0, counts.get(2));
+
+    counts.reset();
+    assert_eq!(0, counts.get(0));
+    assert_eq!(4, counts.bytes_per_count());
+}
+
+#[test]
+fn record_widens_on_first_overflow() {
+    let mut h = widening(1, 100_000, 3);
+    h.record_value_with_count(1000, 255).unwrap();
+    assert_eq!(1, h.counts.bytes_per_count());
+
+    h.record_single_value(1000).unwrap();
+
+    assert_eq!(2, h.counts.bytes_per_count());
+    assert_eq!(256, h.get_count_at_value(1000).unwrap());
+    assert_eq!(256, h.get_count());
+}
+
+#[test]
+fn matches_vec_histogram() {
+    let mut h = widening(1, 1 << 40, 3);
+    let mut expected = SimpleHdrHistogram::<u64>::new(1, 1 << 40, 3);
+    for &(v, c) in &[(0, 3), (7, 300), (5000, 1), (100_000, 70_000), (1 << 35, 1 << 33)] {
+        h.record_value_with_count(v, c).unwrap();
+        expected.record_value_with_count(v, c).unwrap();
+    }
+
+    assert_eq!(8, h.counts.bytes_per_count());
+    assert_eq!(expected.get_count(), h.get_count());
+    assert_eq!(expected.get_max(), h.get_max());
+    assert_eq!(expected.get_min_non_zero(), h.get_min_non_zero());
+    assert_eq!(expected.mean(), h.mean());
+    for &p in &[0.0, 10.0, 50.0, 99.0, 100.0] {
+        assert_eq!(expected.get_value_at_percentile(p), h.get_value_at_percentile(p), "{}", p);
+    }
+    let iterated =
+        |v: HistogramIterationValue<u64>| (v.value_iterated_to, v.count_at_value_iterated_to);
+    assert_eq!(expected.recorded_values().into_iter().map(&iterated).collect::<Vec<_>>(),
+               h.recorded_values().into_iter().map(&iterated).collect::<Vec<_>>());
+    assert_eq!(expected.percentiles(5).into_iter().map(&iterated).collect::<Vec<_>>(),
+               h.percentiles(5).into_iter().map(&iterated).collect::<Vec<_>>());
+}
+
+#[test]
+fn add_across_storage() {
+    let mut h = widening(1, 100_000, 3);
+    h.record_value_with_count(1000, 200).unwrap();
+    let mut other = SimpleHdrHistogram::<u64>::new(1, 100_000, 3);
+    other.record_value_with_count(1000, 100).unwrap();
+
+    h.add(&other).unwrap();
+    other += &h;
+
+    assert_eq!(300, h.get_count_at_value(1000).unwrap());
+    assert_eq!(2, h.counts.bytes_per_count());
+    assert_eq!(400, other.get_count_at_value(1000).unwrap());
+}
+
+#[test]
+fn shift_and_resize() {
+    let mut h = widening(1, 1 << 20, 3);
+    h.set_auto_resize(true);
+    h.record_value_with_count(5000, 1000).unwrap();
+    h.shift_values_left(3).unwrap();
+
+    h.record_single_value(1 << 40).unwrap();
+
+    assert_eq!(1000, h.get_count_at_value(40_000).unwrap());
+    assert_eq!(1, h.get_count_at_value(1 << 40).unwrap());
+    assert_eq!(2, h.counts.bytes_per_count());
+}
+
+#[test]
+fn decode_into_widening() {
+    let mut original = SimpleHdrHistogram::<u64>::new(1, 100_000, 3);
+    original.record_value_with_count(3000, 100_000).unwrap();
+    original.record_single_value(5).unwrap();
+
+    let h = AutoWideningHistogram::decode(&original.encode().unwrap()).unwrap();
+
+    assert_eq!(100_000, h.get_count_at_value(3000).unwrap());
+    assert_eq!(1, h.get_count_at_value(5).unwrap());
+    assert_eq!(4, h.counts.bytes_per_count());
+}
+
+#[test]
+fn packed_counts_only_allocate_non_zero_pages() {
+    let mut counts = PackedCounts::<u64>::with_len(10_000);
+    assert_eq!(0, counts.allocated_len());
+
+    counts.set(5, 0);
+    assert_eq!(0, counts.allocated_len());
+    counts.set(5, 3);
+    counts.set(9_999, 4);
+
+    assert_eq!(3, counts.get(5));
+    assert_eq!(4, counts.get(9_999));
+    assert_eq!(0, counts.get(6));
+    assert!(counts.allocated_len() < 1000, "{}", counts.allocated_len());
+}
+
+#[test]
+fn packed_counts_grow_and_reset() {
+    let mut counts = PackedCounts::<u8>::with_len(300);
+    counts.set(299, 1);
+    counts.grow(5000);
+    counts.set(4999, 2);
+
+    assert_eq!(5000, counts.len());
+    assert_eq!(1, counts.get(299));
+    assert_eq!(2, counts.get(4999));
+
+    counts.reset();
+    assert_eq!(0, counts.get(299));
+    assert_eq!(0, counts.allocated_len());
+    assert_eq!(5000, counts.len());
+}
+
+#[test]
+fn packed_histogram_high_precision_full_range() {
+    let mut h = PackedHistogram::<u64>::new_with_counts(1, u64::max_value(), 5);
+    h.record_value_with_count(1_000_000, 5).unwrap();
+    h.record_single_value(1 << 50).unwrap();
+
+    assert!(h.counts.len() > 1_000_000, "{}", h.counts.len());
+    assert!(h.counts.allocated_len() < 1000, "{}", h.counts.allocated_len());
+    assert_eq!(6, h.get_count());
+    assert_eq!(h.highest_equivalent_value(1_000_000), h.get_value_at_percentile(50.0));
+    assert_eq!(h.highest_equivalent_value(1 << 50), h.get_value_at_percentile(100.0));
+}
+
+#[test]
+fn packed_matches_vec_histogram() {
+    let mut h = PackedHistogram::<u32>::new_with_counts(1, 1 << 40, 3);
+    let mut expected = SimpleHdrHistogram::<u32>::new(1, 1 << 40, 3);
+    for &(v, c) in &[(0, 3), (7, 300), (5000, 1), (100_000, 70_000), (1 << 35, 9)] {
+        h.record_value_with_count(v, c).unwrap();
+        expected.record_value_with_count(v, c).unwrap();
+    }
+    h.shift_values_left(2).unwrap();
+    expected.shift_values_left(2).unwrap();
+
+    assert_eq!(expected.get_count(), h.get_count());
+    assert_eq!(expected.get_max(), h.get_max());
+    assert_eq!(expected.get_min_non_zero(), h.get_min_non_zero());
+    for &p in &[0.0, 10.0, 50.0, 99.0, 100.0] {
+        assert_eq!(expected.get_value_at_percentile(p), h.get_value_at_percentile(p), "{}", p);
+    }
+    let iterated =
+        |v: HistogramIterationValue<u32>| (v.value_iterated_to, v.count_at_value_iterated_to);
+    assert_eq!(expected.recorded_values().into_iter().map(&iterated).collect::<Vec<_>>(),
+               h.recorded_values().into_iter().map(&iterated).collect::<Vec<_>>());
+    assert_eq!(expected.encode().unwrap(), h.encode().unwrap());
+}
+
+#[test]
+fn packed_histogram_reset_frees_pages() {
+    let mut h = PackedHistogram::<u64>::new_with_counts(1, 1 << 40, 3);
+    h.record_single_value(12_345).unwrap();
+    assert!(h.counts.allocated_len() > 0);
+
+    h.reset();
+
+    assert_eq!(0, h.counts.allocated_len());
+    assert_eq!(0, h.get_count());
+}
@@ -0,0 +1,383 @@
+use hdr_histogram::simple_hdr_histogram::*;
+
+/// The lowest value in the covered range before anything has been recorded. Any value would do;
+/// this one is roughly in the middle of the exponent range, so the first recorded value is
+/// unlikely to be near the edges.
+const INITIAL_LOWEST_VALUE_IN_AUTO_RANGE_POW2: i32 = 800;
+
+/// Covering values any higher could make the top of the covered range infinite.
+const HIGHEST_ALLOWED_VALUE_EVER_POW2: i32 = 1022;
+
+/// The most binary orders of magnitude to shift by in one step when adjusting the covered range.
+const MAX_SHIFT_STEP: u32 = 50;
+
+/// A histogram of non-negative `f64` values, as in the Java `DoubleHistogram`.
+///
+/// Rather than covering a fixed range of values, it covers a fixed dynamic range: the highest
+/// value it can hold is `highest_to_lowest_value_ratio` times the lowest non-zero value it can
+/// hold, with `num_significant_digits` of precision in between. Where that range sits is chosen
+/// automatically to fit the values recorded. For instance, a histogram with a ratio of 1000 can
+/// hold values from 0.001 to 1, or from 1000 to 1000000, but not both. Zero can always be
+/// recorded.
+///
+/// Values are recorded in an internal `SimpleHdrHistogram<T>`, scaled by a power of 2 so that the
+/// covered range maps onto its integer values. Moving the covered range shifts the internal
+/// histogram's counts, which only changes its normalizing index offset rather than moving them.
+#[derive(Debug)]
+pub struct DoubleHistogram<T: HistogramCount> {
+    configured_highest_to_lowest_value_ratio: u64,
+    /// The lowest non-zero value that can be recorded without moving the covered range.
+    current_lowest_value_in_auto_range: f64,
+    /// The lowest value above the covered range.
+    current_highest_value_limit_in_auto_range: f64,
+    /// Inverse of the internal histogram's integer to double value conversion ratio.
+    double_to_integer_value_conversion_ratio: f64,
+    integer_values_histogram: SimpleHdrHistogram<T>,
+}
+
+impl<T: HistogramCount> DoubleHistogram<T> {
+    /// highest_to_lowest_value_ratio: must be >= 2
+    /// num_significant_digits: must be <= 5
+    ///
+    /// Panics if the configuration is invalid. Use `try_new` to handle that instead.
+    pub fn new(highest_to_lowest_value_ratio: u64, num_significant_digits: u32)
+            -> DoubleHistogram<T> {
+        match DoubleHistogram::try_new(highest_to_lowest_value_ratio, num_significant_digits) {
+            Ok(h) => h,
+            Err(e) => panic!("Invalid histogram configuration: {}", e)
+        }
+    }
+
+    /// Like `new`, but returns an error rather than panicking on an invalid configuration.
+    pub fn try_new(highest_to_lowest_value_ratio: u64, num_significant_digits: u32)
+            -> Result<DoubleHistogram<T>, CreationError> {
+        if highest_to_lowest_value_ratio < 2 {
+            return Err(CreationError::HighToLowRatioTooSmall);
+        }
+        if num_significant_digits > 5 {
+            return Err(CreationError::SigFigExceedsMax);
+        }
+        if highest_to_lowest_value_ratio as f64 * 10_f64.powi(num_significant_digits as i32)
+                >= (1_u64 << 61) as f64 {
+            return Err(CreationError::RatioAndSigFigExceedRange);
+        }
+
+        // The lower half of the internal histogram's bucket 0 doesn't have the required
+        // precision, so the covered range starts at the top half of bucket 0. The internal
+        // histogram's range must also cover one more binary order of magnitude than the
+        // configured ratio: e.g. covering [0.9, 2.1) takes [0.5, 1.0), [1.0, 2.0) and [2.0, 4.0),
+        // which is a ratio of 8 even though 2.1 / 0.9 < 4.
+        let lowest_tracking_integer_value =
+            DoubleHistogram::<T>::sub_bucket_half_count(num_significant_digits);
+        let internal_highest_to_lowest_value_ratio =
+            DoubleHistogram::<T>::internal_highest_to_lowest_value_ratio(
+                highest_to_lowest_value_ratio);
+        // The ratio and digits were checked above, so this is well below 2^64.
+        let integer_value_range = lowest_tracking_integer_value
+            * internal_highest_to_lowest_value_ratio;
+
+        let integer_values_histogram = SimpleHdrHistogram::try_new(1, integer_value_range - 1,
+                                                                   num_significant_digits)?;
+        let mut h = DoubleHistogram {
+            configured_highest_to_lowest_value_ratio: highest_to_lowest_value_ratio,
+            current_lowest_value_in_auto_range: 0.0,
+            current_highest_value_limit_in_auto_range: 0.0,
+            double_to_integer_value_conversion_ratio: 0.0,
+            integer_values_histogram: integer_values_histogram,
+        };
+        h.reset_trackable_value_range();
+        Ok(h)
+    }
+
+    pub fn get_highest_to_lowest_value_ratio(&self) -> u64 {
+        self.configured_highest_to_lowest_value_ratio
+    }
+
+    pub fn get_num_significant_digits(&self) -> u32 {
+        self.integer_values_histogram.num_significant_digits
+    }
+
+    /// The lowest non-zero value that can currently be recorded without moving the covered range.
+    pub fn get_current_lowest_value_in_auto_range(&self) -> f64 {
+        self.current_lowest_value_in_auto_range
+    }
+
+    /// The lowest value above the currently covered range.
+    pub fn get_current_highest_value_limit_in_auto_range(&self) -> f64 {
+        self.current_highest_value_limit_in_auto_range
+    }
+
+    pub fn record(&mut self, value: f64) -> Result<(), RecordError> {
+        self.record_with_count(value, T::one())
+    }
+
+    /// Record `count` occurrences of `value`, moving the covered range to include `value` if
+    /// needed.
+    ///
+    /// Fails with `RecordError::ValueOutOfRange` without recording anything if `value` is negative
+    /// or NaN, or if the covered range can't include it without leaving out values that were
+    /// already recorded.
+    pub fn record_with_count(&mut self, value: f64, count: T) -> Result<(), RecordError> {
+        if value.is_nan() || value < 0.0 {
+            return Err(RecordError::ValueOutOfRange);
+        }
+        if value < self.current_lowest_value_in_auto_range
+                || value >= self.current_highest_value_limit_in_auto_range {
+            self.auto_adjust_range_for_value(value)?;
+        }
+
+        let integer_value = (value * self.double_to_integer_value_conversion_ratio) as u64;
+        self.integer_values_histogram.record_value_with_count(integer_value, count)
+    }
+
+    /// Returns the number of values recorded.
+    pub fn get_count(&self) -> u128 {
+        self.integer_values_histogram.get_count()
+    }
+
+    /// Returns the count at the specified value (as well as other equivalent values). Values
+    /// outside the covered range have a count of 0.
+    pub fn get_count_at_value(&self, value: f64) -> T {
+        if value != 0.0 && !self.is_in_covered_range(value) {
+            return T::zero();
+        }
+        // a value in the covered range is always in the counts array
+        self.integer_values_histogram.get_count_at_value(self.to_integer_value(value))
+            .unwrap_or(T::zero())
+    }
+
+    /// Returns the highest value equivalent to the max value recorded. Undefined if no values
+    /// have been recorded.
+    pub fn get_max(&self) -> f64 {
+        let max = self.integer_values_histogram.get_max();
+        if max == 0 {
+            return 0.0;
+        }
+        self.highest_equivalent_double_value(max)
+    }
+
+    /// Returns the lowest value equivalent to the lowest non-zero value recorded. Undefined if no
+    /// values have been recorded.
+    pub fn get_min_non_zero(&self) -> f64 {
+        let h = &self.integer_values_histogram;
+        self.to_double_value(h.lowest_equivalent_value(h.get_min_non_zero()))
+    }
+
+    /// See `HistogramBase::get_value_at_percentile`.
+    pub fn get_value_at_percentile(&self, percentile: f64) -> f64 {
+        self.to_double_value(self.integer_values_histogram.get_value_at_percentile(percentile))
+    }
+
+    /// See `HistogramBase::get_percentile_below`.
+    pub fn get_percentile_below(&self, value: f64) -> f64 {
+        if value >= self.current_highest_value_limit_in_auto_range {
+            return 100.0;
+        }
+        self.integer_values_histogram.get_percentile_below(self.to_integer_value(value))
+    }
+
+    /// See `HistogramBase::mean`.
+    pub fn mean(&self) -> f64 {
+        self.integer_values_histogram.mean() * self.integer_to_double_value_conversion_ratio()
+    }
+
+    /// See `HistogramBase::stdev`.
+    pub fn stdev(&self) -> f64 {
+        self.integer_values_histogram.stdev() * self.integer_to_double_value_conversion_ratio()
+    }
+
+    /// Returns the value at the 50th percentile.
+    pub fn median(&self) -> f64 {
+        self.get_value_at_percentile(50.0)
+    }
+
+    /// Returns the lowest value equivalent to the provided value (equivalent meaning will store
+    /// counts in the same memory location), for values in the covered range.
+    pub fn lowest_equivalent_value(&self, value: f64) -> f64 {
+        let h = &self.integer_values_histogram;
+        self.to_double_value(h.lowest_equivalent_value(self.to_integer_value(value)))
+    }
+
+    /// Returns the highest value equivalent to the provided value (equivalent meaning will store
+    /// counts in the same memory location), for values in the covered range.
+    pub fn highest_equivalent_value(&self, value: f64) -> f64 {
+        self.highest_equivalent_double_value(self.to_integer_value(value))
+    }
+
+    /// Iterate across all recorded values. Use `double_value_iterated_to` on each item to get the
+    /// value it represents.
+    pub fn recorded_values(&self) -> RecordedValues<'_, T> {
+        self.integer_values_histogram.recorded_values()
+    }
+
+    /// Iterate across all values in the covered range, recorded or not. Use
+    /// `double_value_iterated_to` on each item to get the value it represents.
+    pub fn all_values(&self) -> AllValues<'_, T> {
+        self.integer_values_histogram.all_values()
+    }
+
+    /// Iterate across percentiles until all recorded values are exhausted. Use
+    /// `double_value_iterated_to` on each item to get the value it represents.
+    pub fn percentiles(&self, percentile_ticks_per_half_distance: u32) -> Percentiles<'_, T> {
+        self.integer_values_histogram.percentiles(percentile_ticks_per_half_distance)
+    }
+
+    /// Clear all recorded values. The covered range moves to fit whatever is recorded next.
+    pub fn reset(&mut self) {
+        self.integer_values_histogram.reset();
+        self.reset_trackable_value_range();
+    }
+
+    fn integer_to_double_value_conversion_ratio(&self) -> f64 {
+        self.integer_values_histogram.integer_to_double_value_conversion_ratio
+    }
+
+    fn to_double_value(&self, integer_value: u64) -> f64 {
+        integer_value as f64 * self.integer_to_double_value_conversion_ratio()
+    }
+
+    fn to_integer_value(&self, value: f64) -> u64 {
+        (value * self.double_to_integer_value_conversion_ratio) as u64
+    }
+
+    /// The integer histogram's highest equivalent value is one integer below the next bucket, but
+    /// every double in between is equivalent too.
+    fn highest_equivalent_double_value(&self, integer_value: u64) -> f64 {
+        let next_non_equivalent_value = self.to_double_value(
+            self.integer_values_histogram.next_non_equivalent_value(integer_value));
+        // next_non_equivalent_value is positive, so this is the largest f64 below it
+        f64::from_bits(next_non_equivalent_value.to_bits() - 1)
+    }
+
+    fn is_in_covered_range(&self, value: f64) -> bool {
+        value >= self.current_lowest_value_in_auto_range
+            && value < self.current_highest_value_limit_in_auto_range
+    }
+
+    fn reset_trackable_value_range(&mut self) {
+        let lowest_value_in_auto_range = 2_f64.powi(INITIAL_LOWEST_VALUE_IN_AUTO_RANGE_POW2);
+        let internal_highest_to_lowest_value_ratio =
+            DoubleHistogram::<T>::internal_highest_to_lowest_value_ratio(
+                self.configured_highest_to_lowest_value_ratio);
+        self.set_trackable_value_range(
+            lowest_value_in_auto_range,
+            lowest_value_in_auto_range * internal_highest_to_lowest_value_ratio as f64);
+    }
+
+    fn set_trackable_value_range(&mut self, lowest_value_in_auto_range: f64,
+                                 highest_value_limit_in_auto_range: f64) {
+        self.current_lowest_value_in_auto_range = lowest_value_in_auto_range;
+        self.current_highest_value_limit_in_auto_range = highest_value_limit_in_auto_range;
+        // the lowest value in the covered range maps to the bottom of the top half of bucket 0
+        let integer_to_double_value_conversion_ratio = lowest_value_in_auto_range
+            / self.integer_values_histogram.sub_bucket_half_count as f64;
+        self.integer_values_histogram.integer_to_double_value_conversion_ratio =
+            integer_to_double_value_conversion_ratio;
+        self.double_to_integer_value_conversion_ratio =
+            1.0 / integer_to_double_value_conversion_ratio;
+    }
+
+    fn auto_adjust_range_for_value(&mut self, value: f64) -> Result<(), RecordError> {
+        // zero is always in range
+        if value == 0.0 {
+            return Ok(());
+        }
+
+        if value < self.current_lowest_value_in_auto_range {
+            while value < self.current_lowest_value_in_auto_range {
+                let shift_amount = self.find_capped_containing_binary_order_of_magnitude(
+                    (self.current_lowest_value_in_auto_range / value).ceil() - 1.0);
+                self.shift_covered_range_to_the_right(shift_amount)?;
+            }
+        } else if value >= self.current_highest_value_limit_in_auto_range {
+            if value > 2_f64.powi(HIGHEST_ALLOWED_VALUE_EVER_POW2) {
+                return Err(RecordError::ValueOutOfRange);
+            }
+            while value >= self.current_highest_value_limit_in_auto_range {
+                // A value that's an exact multiple of the limit belongs with the next level up,
+                // so compute the ratio from a value 1 ulp bigger to round it up, without rounding
+                // up values just below an exact multiple.
+                let shift_amount = self.find_capped_containing_binary_order_of_magnitude(
+                    ((value + ulp(value)) / self.current_highest_value_limit_in_auto_range).ceil()
+                        - 1.0);
+                self.shift_covered_range_to_the_left(shift_amount)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Move the covered range down by `binary_orders_of_magnitude`. The recorded integer values
+    /// are shifted left by the same amount so that they still represent the same values.
+    fn shift_covered_range_to_the_right(&mut self, binary_orders_of_magnitude: u32)
+            -> Result<(), RecordError> {
+        let shift_multiplier = 1.0 / (1_u64 << binary_orders_of_magnitude) as f64;
+        let new_lowest_value_in_auto_range =
+            self.current_lowest_value_in_auto_range * shift_multiplier;
+        // Values this small can't be covered without the conversion ratios becoming infinite.
+        let new_integer_to_double_value_conversion_ratio =
+            self.integer_to_double_value_conversion_ratio() * shift_multiplier;
+        if !(1.0 / new_integer_to_double_value_conversion_ratio).is_finite() {
+            return Err(RecordError::ValueOutOfRange);
+        }
+
+        self.integer_values_histogram.shift_values_left(binary_orders_of_magnitude)?;
+        self.set_trackable_value_range(
+            new_lowest_value_in_auto_range,
+            self.current_highest_value_limit_in_auto_range * shift_multiplier);
+        Ok(())
+    }
+
+    /// Move the covered range up by `binary_orders_of_magnitude`. The recorded integer values are
+    /// shifted right by the same amount so that they still represent the same values.
+    fn shift_covered_range_to_the_left(&mut self, binary_orders_of_magnitude: u32)
+            -> Result<(), RecordError> {
+        let shift_multiplier = (1_u64 << binary_orders_of_magnitude) as f64;
+        self.integer_values_histogram.shift_values_right(binary_orders_of_magnitude)?;
+        let new_lowest_value_in_auto_range =
+            self.current_lowest_value_in_auto_range * shift_multiplier;
+        let new_highest_value_limit_in_auto_range =
+            self.current_highest_value_limit_in_auto_range * shift_multiplier;
+        self.set_trackable_value_range(new_lowest_value_in_auto_range,
+                                       new_highest_value_limit_in_auto_range);
+        Ok(())
+    }
+
+    /// The number of binary orders of magnitude in `ratio`, capped so that one shift can't move
+    /// the covered range further than its own dynamic range.
+    fn find_capped_containing_binary_order_of_magnitude(&self, ratio: f64) -> u32 {
+        let ratio_cap = self.configured_highest_to_lowest_value_ratio;
+        if ratio > ratio_cap as f64 {
+            return 63 - ratio_cap.leading_zeros();
+        }
+        if ratio > (1_u64 << MAX_SHIFT_STEP) as f64 {
+            return MAX_SHIFT_STEP;
+        }
+        // Always shift by at least 1 so that adjusting the range can't get stuck.
+        cmp::max(1, containing_binary_order_of_magnitude(ratio.ceil() as u64))
+    }
+
+    /// The internal histogram covers one more binary order of magnitude than the configured
+    /// ratio.
+    fn internal_highest_to_lowest_value_ratio(highest_to_lowest_value_ratio: u64) -> u64 {
+        1 << (containing_binary_order_of_magnitude(highest_to_lowest_value_ratio) + 1)
+    }
+
+    /// The internal histogram's sub bucket half count, as calculated in
+    /// `SimpleHdrHistogram::try_new`.
+    fn sub_bucket_half_count(num_significant_digits: u32) -> u64 {
+        let largest_value_with_single_unit_resolution = 2_u64 * 10_u64.pow(num_significant_digits);
+        let sub_bucket_count_magnitude: u32 =
+            ((largest_value_with_single_unit_resolution as f64).ln() / 2_f64.ln()).ceil() as u32;
+        1 << (cmp::max(sub_bucket_count_magnitude, 1) - 1)
+    }
+}
+
+/// The smallest k such that 2^k > `n`.
+fn containing_binary_order_of_magnitude(n: u64) -> u32 {
+    64 - n.leading_zeros()
+}
+
+/// The distance from a positive, finite `value` to the next larger `f64`.
+fn ulp(value: f64) -> f64 {
+    f64::from_bits(value.to_bits() + 1) - value
+}
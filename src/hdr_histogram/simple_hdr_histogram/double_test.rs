@@ -0,0 +1,176 @@
+use hdr_histogram::simple_hdr_histogram::*;
+
+/// Equivalent values at 3 significant digits are within 0.1% of each other.
+fn assert_equivalent(expected: f64, actual: f64) {
+    assert!((expected - actual).abs() <= expected * 0.001,
+            "expected {} but got {}", expected, actual);
+}
+
+#[test]
+fn try_new_invalid() {
+    assert_eq!(CreationError::HighToLowRatioTooSmall,
+               DoubleHistogram::<u64>::try_new(1, 3).unwrap_err());
+    assert_eq!(CreationError::SigFigExceedsMax,
+               DoubleHistogram::<u64>::try_new(1000, 6).unwrap_err());
+    assert_eq!(CreationError::RatioAndSigFigExceedRange,
+               DoubleHistogram::<u64>::try_new(1 << 60, 3).unwrap_err());
+}
+
+#[test]
+#[should_panic(expected = "Invalid histogram configuration")]
+fn new_invalid_panics() {
+    DoubleHistogram::<u64>::new(1, 3);
+}
+
+#[test]
+fn record_and_query() {
+    let mut h = DoubleHistogram::<u64>::new(1_000_000, 3);
+    h.record(1.5).unwrap();
+    h.record_with_count(3.25, 2).unwrap();
+    h.record(1000.0).unwrap();
+
+    assert_eq!(4, h.get_count());
+    assert_eq!(2, h.get_count_at_value(3.25));
+    assert_eq!(0, h.get_count_at_value(10.0));
+    assert_equivalent(1000.0, h.get_max());
+    assert_equivalent(1.5, h.get_min_non_zero());
+    assert_equivalent(1.5, h.get_value_at_percentile(0.0));
+    assert_equivalent(3.25, h.median());
+    assert_equivalent(1000.0, h.get_value_at_percentile(100.0));
+    assert_equivalent((1.5 + 3.25 * 2.0 + 1000.0) / 4.0, h.mean());
+    assert_eq!(75.0, h.get_percentile_below(3.25));
+}
+
+#[test]
+fn equivalent_values_bracket_value() {
+    let mut h = DoubleHistogram::<u64>::new(1000, 3);
+    h.record(123.456).unwrap();
+
+    assert!(h.lowest_equivalent_value(123.456) <= 123.456);
+    assert!(h.highest_equivalent_value(123.456) >= 123.456);
+    assert_equivalent(123.456, h.lowest_equivalent_value(123.456));
+    assert_equivalent(123.456, h.highest_equivalent_value(123.456));
+}
+
+#[test]
+fn covered_range_fits_first_value() {
+    let mut h = DoubleHistogram::<u64>::new(1000, 3);
+    h.record(5.0).unwrap();
+
+    assert!(h.get_current_lowest_value_in_auto_range() <= 5.0);
+    assert!(h.get_current_highest_value_limit_in_auto_range() > 5.0 * 1000.0 / 2.0);
+}
+
+#[test]
+fn covered_range_moves_down_for_smaller_values() {
+    let mut h = DoubleHistogram::<u64>::new(1_000_000, 3);
+    h.record(1000.0).unwrap();
+    h.record(0.001).unwrap();
+
+    assert_eq!(2, h.get_count());
+    assert_eq!(1, h.get_count_at_value(1000.0));
+    assert_eq!(1, h.get_count_at_value(0.001));
+    assert_equivalent(1000.0, h.get_max());
+    assert_equivalent(0.001, h.get_min_non_zero());
+}
+
+#[test]
+fn covered_range_moves_up_for_larger_values() {
+    let mut h = DoubleHistogram::<u64>::new(1_000_000, 3);
+    h.record(0.5).unwrap();
+    h.record(1.0e4).unwrap();
+    h.record(2.5e5).unwrap();
+
+    assert_eq!(3, h.get_count());
+    assert_equivalent(0.5, h.get_min_non_zero());
+    assert_equivalent(2.5e5, h.get_max());
+    assert_equivalent(1.0e4, h.median());
+}
+
+#[test]
+fn value_beyond_dynamic_range_is_error() {
+    let mut h = DoubleHistogram::<u64>::new(1000, 3);
+    h.record(1.0).unwrap();
+
+    assert_eq!(Err(RecordError::ValueOutOfRange), h.record(1.0e7));
+    assert_eq!(Err(RecordError::ValueOutOfRange), h.record(1.0e-7));
+    assert_eq!(1, h.get_count());
+    assert_equivalent(1.0, h.get_max());
+    assert_eq!(1, h.get_count_at_value(1.0));
+}
+
+#[test]
+fn negative_and_nan_are_errors() {
+    let mut h = DoubleHistogram::<u64>::new(1000, 3);
+
+    assert_eq!(Err(RecordError::ValueOutOfRange), h.record(-1.0));
+    assert_eq!(Err(RecordError::ValueOutOfRange), h.record(f64::NAN));
+    assert_eq!(Err(RecordError::ValueOutOfRange), h.record(f64::INFINITY));
+    assert_eq!(0, h.get_count());
+}
+
+#[test]
+fn zero_survives_range_moves() {
+    let mut h = DoubleHistogram::<u64>::new(1000, 3);
+    h.record(0.0).unwrap();
+    h.record(5.0).unwrap();
+    h.record(0.01).unwrap();
+
+    assert_eq!(3, h.get_count());
+    assert_eq!(1, h.get_count_at_value(0.0));
+    assert_eq!(0.0, h.get_value_at_percentile(0.0));
+    assert_equivalent(0.01, h.get_min_non_zero());
+    assert_equivalent(5.0, h.get_max());
+}
+
+#[test]
+fn recorded_values_are_converted() {
+    let mut h = DoubleHistogram::<u64>::new(1_000_000, 3);
+    let values = [0.25, 7.5, 300.0, 12_345.0];
+    for &v in values.iter() {
+        h.record(v).unwrap();
+    }
+
+    let iterated: Vec<f64> = h.recorded_values().into_iter()
+        .map(|v| v.double_value_iterated_to())
+        .collect();
+
+    assert_eq!(values.len(), iterated.len());
+    for (&expected, &actual) in values.iter().zip(iterated.iter()) {
+        assert_equivalent(expected, actual);
+    }
+}
+
+#[test]
+fn percentiles_end_at_max() {
+    let mut h = DoubleHistogram::<u64>::new(1_000_000, 3);
+    for i in 1..1001 {
+        h.record(i as f64 / 100.0).unwrap();
+    }
+
+    let last = h.percentiles(5).into_iter().last().unwrap();
+
+    assert_eq!(100.0, last.percentile);
+    assert_equivalent(10.0, last.double_value_iterated_to());
+}
+
+#[test]
+fn reset_allows_new_range() {
+    let mut h = DoubleHistogram::<u64>::new(1000, 3);
+    h.record(1.0).unwrap();
+    h.reset();
+
+    assert_eq!(0, h.get_count());
+    h.record(1.0e7).unwrap();
+    assert_eq!(1, h.get_count());
+    assert_equivalent(1.0e7, h.get_max());
+}
+
+#[test]
+fn small_count_type() {
+    let mut h = DoubleHistogram::<u8>::new(1000, 2);
+    h.record_with_count(2.0, 255).unwrap();
+
+    assert_eq!(Err(RecordError::CountOverflow), h.record(2.0));
+    assert_eq!(255, h.get_count_at_value(2.0));
+}
@@ -0,0 +1,232 @@
+use std::time::Duration;
+use std::time::Instant;
+
+use hdr_histogram::simple_hdr_histogram::*;
+
+/// The unit that a `DurationHistogram` records durations in. Durations are truncated to a whole
+/// number of units.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum DurationUnit {
+    Nanoseconds,
+    Microseconds,
+    Milliseconds,
+    Seconds,
+}
+
+impl DurationUnit {
+    fn nanos_per_unit(self) -> u128 {
+        match self {
+            DurationUnit::Nanoseconds => 1,
+            DurationUnit::Microseconds => 1_000,
+            DurationUnit::Milliseconds => 1_000_000,
+            DurationUnit::Seconds => 1_000_000_000,
+        }
+    }
+
+    /// None if there are more than u64::max_value() units in `duration`.
+    fn to_units(self, duration: Duration) -> Option<u64> {
+        let units = duration.as_nanos() / self.nanos_per_unit();
+        if units > u64::max_value() as u128 {
+            None
+        } else {
+            Some(units as u64)
+        }
+    }
+
+    fn to_duration(self, units: u64) -> Duration {
+        match self {
+            DurationUnit::Nanoseconds => Duration::from_nanos(units),
+            DurationUnit::Microseconds => Duration::from_micros(units),
+            DurationUnit::Milliseconds => Duration::from_millis(units),
+            DurationUnit::Seconds => Duration::from_secs(units),
+        }
+    }
+
+    /// For means and standard deviations, which needn't be a whole number of units.
+    fn fractional_to_duration(self, units: f64) -> Duration {
+        let secs = units * self.nanos_per_unit() as f64 / 1e9;
+        Duration::try_from_secs_f64(secs).unwrap_or(Duration::MAX)
+    }
+}
+
+/// A histogram of `Duration`s, such as request latencies, recorded as a whole number of `unit`s in
+/// an internal `SimpleHdrHistogram<T>`. Query results are converted back to `Duration`s.
+///
+/// Besides recording durations measured elsewhere, it can time a closure with `time`, or time a
+/// scope with the `DurationTimer` returned by `start_timer`.
+#[derive(Debug)]
+pub struct DurationHistogram<T: HistogramCount> {
+    unit: DurationUnit,
+    histogram: SimpleHdrHistogram<T>,
+}
+
+impl<T: HistogramCount> DurationHistogram<T> {
+    /// lowest_discernible_duration: must be at least 1 unit
+    /// highest_trackable_duration: must be >= 2 * lowest_discernible_duration
+    /// num_significant_digits: must be <= 5
+    ///
+    /// Panics if the configuration is invalid. Use `try_new` to handle that instead.
+    pub fn new(unit: DurationUnit, lowest_discernible_duration: Duration,
+               highest_trackable_duration: Duration, num_significant_digits: u32)
+            -> DurationHistogram<T> {
+        match DurationHistogram::try_new(unit, lowest_discernible_duration,
+                                         highest_trackable_duration, num_significant_digits) {
+            Ok(h) => h,
+            Err(e) => panic!("Invalid histogram configuration: {}", e)
+        }
+    }
+
+    /// Like `new`, but returns an error rather than panicking on an invalid configuration.
+    ///
+    /// A highest trackable duration of more than u64::max_value() units is treated as
+    /// u64::max_value() units.
+    pub fn try_new(unit: DurationUnit, lowest_discernible_duration: Duration,
+                   highest_trackable_duration: Duration, num_significant_digits: u32)
+            -> Result<DurationHistogram<T>, CreationError> {
+        // the highest can't be more than u64::max_value() units either, so it's less than twice
+        // such a lowest
+        let lowest_discernible_value = unit.to_units(lowest_discernible_duration)
+            .ok_or(CreationError::HighLessThanTwiceLow)?;
+        let highest_trackable_value = unit.to_units(highest_trackable_duration)
+            .unwrap_or(u64::max_value());
+        Ok(DurationHistogram {
+            unit: unit,
+            histogram: SimpleHdrHistogram::try_new(lowest_discernible_value,
+                                                   highest_trackable_value,
+                                                   num_significant_digits)?,
+        })
+    }
+
+    pub fn get_unit(&self) -> DurationUnit {
+        self.unit
+    }
+
+    /// The internal histogram, whose values are in units of `get_unit()`.
+    pub fn histogram(&self) -> &SimpleHdrHistogram<T> {
+        &self.histogram
+    }
+
+    /// The internal histogram, e.g. to change its out of range policy. Its values are in units of
+    /// `get_unit()`.
+    pub fn histogram_mut(&mut self) -> &mut SimpleHdrHistogram<T> {
+        &mut self.histogram
+    }
+
+    pub fn record(&mut self, duration: Duration) -> Result<(), RecordError> {
+        self.record_with_count(duration, T::one())
+    }
+
+    /// Record `count` occurrences of `duration`. Fails with `RecordError::ValueOutOfRange` if
+    /// `duration` is more than u64::max_value() units, and otherwise as per
+    /// `SimpleHdrHistogram::record_value_with_count`.
+    pub fn record_with_count(&mut self, duration: Duration, count: T) -> Result<(), RecordError> {
+        let value = self.unit.to_units(duration).ok_or(RecordError::ValueOutOfRange)?;
+        self.histogram.record_value_with_count(value, count)
+    }
+
+    /// Record `duration`, correcting for coordinated omission as per
+    /// `SimpleHdrHistogram::record_value_with_expected_interval`.
+    pub fn record_with_expected_interval(&mut self, duration: Duration,
+                                         expected_interval: Duration) -> Result<(), RecordError> {
+        let value = self.unit.to_units(duration).ok_or(RecordError::ValueOutOfRange)?;
+        let expected_interval = self.unit.to_units(expected_interval)
+            .ok_or(RecordError::ValueOutOfRange)?;
+        self.histogram.record_value_with_expected_interval(value, expected_interval)
+    }
+
+    /// Call `f` and record how long it took, returning its result.
+    ///
+    /// If recording fails, `f`'s result is dropped and the error is returned instead. To keep the
+    /// result regardless, use `start_timer` and `DurationTimer::stop`.
+    pub fn time<R, F: FnOnce() -> R>(&mut self, f: F) -> Result<R, RecordError> {
+        let start = Instant::now();
+        let result = f();
+        self.record(start.elapsed())?;
+        Ok(result)
+    }
+
+    /// Start timing something. The elapsed time is recorded when the timer is stopped or dropped.
+    pub fn start_timer(&mut self) -> DurationTimer<'_, T> {
+        DurationTimer {
+            histogram: self,
+            start: Instant::now(),
+            stopped: false,
+        }
+    }
+
+    pub fn get_count(&self) -> u128 {
+        self.histogram.get_count()
+    }
+
+    pub fn get_max(&self) -> Duration {
+        self.unit.to_duration(self.histogram.get_max())
+    }
+
+    pub fn get_min_non_zero(&self) -> Duration {
+        self.unit.to_duration(self.histogram.get_min_non_zero())
+    }
+
+    pub fn get_value_at_percentile(&self, percentile: f64) -> Duration {
+        self.unit.to_duration(self.histogram.get_value_at_percentile(percentile))
+    }
+
+    /// Returns the percentage of recorded durations that are less than or equivalent to
+    /// `duration`.
+    pub fn get_percentile_below(&self, duration: Duration) -> f64 {
+        let value = self.unit.to_units(duration).unwrap_or(u64::max_value());
+        self.histogram.get_percentile_below(value)
+    }
+
+    pub fn mean(&self) -> Duration {
+        self.unit.fractional_to_duration(self.histogram.mean())
+    }
+
+    pub fn stdev(&self) -> Duration {
+        self.unit.fractional_to_duration(self.histogram.stdev())
+    }
+
+    pub fn median(&self) -> Duration {
+        self.unit.to_duration(self.histogram.median())
+    }
+
+    pub fn reset(&mut self) {
+        self.histogram.reset();
+    }
+}
+
+/// Times something from `DurationHistogram::start_timer` until it's stopped or dropped, then
+/// records the elapsed time.
+///
+/// Errors can't be returned from `drop`, so if recording fails there, the duration is silently
+/// lost. Use `stop` to see the error, or set an out of range policy on the histogram.
+#[derive(Debug)]
+pub struct DurationTimer<'a, T: HistogramCount + 'a> {
+    histogram: &'a mut DurationHistogram<T>,
+    start: Instant,
+    stopped: bool,
+}
+
+impl<'a, T: HistogramCount + 'a> DurationTimer<'a, T> {
+    /// How long it's been since the timer started.
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Record the elapsed time now, returning it.
+    pub fn stop(mut self) -> Result<Duration, RecordError> {
+        self.stopped = true;
+        let elapsed = self.start.elapsed();
+        self.histogram.record(elapsed)?;
+        Ok(elapsed)
+    }
+}
+
+impl<'a, T: HistogramCount + 'a> Drop for DurationTimer<'a, T> {
+    fn drop(&mut self) {
+        if !self.stopped {
+            // see the struct docs
+            let _ = self.histogram.record(self.start.elapsed());
+        }
+    }
+}
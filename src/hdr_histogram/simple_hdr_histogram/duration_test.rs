@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+use hdr_histogram::simple_hdr_histogram::*;
+
+fn micros_histogram() -> DurationHistogram<u64> {
+    DurationHistogram::new(DurationUnit::Microseconds, Duration::from_micros(1),
+                           Duration::from_secs(60), 3)
+}
+
+#[test]
+fn try_new_invalid() {
+    assert_eq!(CreationError::LowIsZero,
+               DurationHistogram::<u64>::try_new(DurationUnit::Milliseconds,
+                                                 Duration::from_micros(10),
+                                                 Duration::from_secs(1), 3).unwrap_err());
+    assert_eq!(CreationError::HighLessThanTwiceLow,
+               DurationHistogram::<u64>::try_new(DurationUnit::Milliseconds,
+                                                 Duration::from_secs(1),
+                                                 Duration::from_millis(1500), 3).unwrap_err());
+    assert_eq!(CreationError::HighLessThanTwiceLow,
+               DurationHistogram::<u64>::try_new(DurationUnit::Nanoseconds,
+                                                 Duration::from_secs(u64::max_value()),
+                                                 Duration::from_secs(u64::max_value()), 3)
+                   .unwrap_err());
+}
+
+#[test]
+#[should_panic(expected = "Invalid histogram configuration")]
+fn new_invalid_panics() {
+    DurationHistogram::<u64>::new(DurationUnit::Seconds, Duration::from_secs(1),
+                                  Duration::from_secs(1), 3);
+}
+
+#[test]
+fn highest_clamped_to_u64_units() {
+    let h = DurationHistogram::<u64>::new(DurationUnit::Nanoseconds, Duration::from_nanos(1),
+                                          Duration::from_secs(u64::max_value()), 2);
+
+    assert_eq!(u64::max_value(), h.histogram().get_highest_trackable_value());
+}
+
+#[test]
+fn record_in_unit() {
+    let mut h = micros_histogram();
+    h.record(Duration::from_millis(3)).unwrap();
+    h.record_with_count(Duration::from_nanos(250_999), 2).unwrap();
+
+    assert_eq!(DurationUnit::Microseconds, h.get_unit());
+    assert_eq!(3, h.get_count());
+    // truncated to whole microseconds
+    assert_eq!(2, h.histogram().get_count_at_value(250).unwrap());
+    assert_eq!(1, h.histogram().get_count_at_value(3000).unwrap());
+}
+
+#[test]
+fn query_as_durations() {
+    let mut h = micros_histogram();
+    h.record_with_count(Duration::from_micros(100), 3).unwrap();
+    h.record(Duration::from_micros(500)).unwrap();
+
+    assert_eq!(Duration::from_micros(100), h.get_min_non_zero());
+    assert_eq!(Duration::from_micros(100), h.median());
+    assert_eq!(Duration::from_micros(500), h.get_value_at_percentile(100.0));
+    assert_eq!(Duration::from_micros(h.histogram().get_max()), h.get_max());
+    assert_eq!(Duration::from_micros(200), h.mean());
+    assert_eq!(Duration::from_nanos(173_205), h.stdev());
+    assert_eq!(75.0, h.get_percentile_below(Duration::from_micros(100)));
+    assert_eq!(100.0, h.get_percentile_below(Duration::from_secs(u64::max_value())));
+}
+
+#[test]
+fn seconds_unit() {
+    let mut h = DurationHistogram::<u32>::new(DurationUnit::Seconds, Duration::from_secs(1),
+                                              Duration::from_secs(3600), 2);
+    h.record(Duration::from_millis(90_500)).unwrap();
+
+    assert_eq!(Duration::from_secs(90), h.median());
+    assert_eq!(Duration::from_secs(90), h.mean());
+}
+
+#[test]
+fn record_out_of_range() {
+    let mut h = micros_histogram();
+
+    assert_eq!(RecordError::ValueOutOfRange, h.record(Duration::from_secs(3600)).unwrap_err());
+    assert_eq!(RecordError::ValueOutOfRange,
+               h.record(Duration::from_secs(u64::max_value())).unwrap_err());
+    assert_eq!(0, h.get_count());
+}
+
+#[test]
+fn record_with_expected_interval() {
+    let mut h = micros_histogram();
+    h.record_with_expected_interval(Duration::from_micros(400), Duration::from_micros(100))
+        .unwrap();
+
+    assert_eq!(4, h.get_count());
+    assert_eq!(Duration::from_micros(100), h.get_min_non_zero());
+}
+
+#[test]
+fn time_records_and_returns_result() {
+    let mut h = micros_histogram();
+
+    assert_eq!(42, h.time(|| 6 * 7).unwrap());
+    assert_eq!(1, h.get_count());
+}
+
+#[test]
+fn timer_records_on_drop() {
+    let mut h = micros_histogram();
+    {
+        let timer = h.start_timer();
+        assert!(timer.elapsed() < Duration::from_secs(60));
+    }
+
+    assert_eq!(1, h.get_count());
+}
+
+#[test]
+fn timer_stop_records_once() {
+    let mut h = micros_histogram();
+    let elapsed = h.start_timer().stop().unwrap();
+
+    assert_eq!(1, h.get_count());
+    let units = elapsed.as_micros() as u64;
+    assert_eq!(1, h.histogram().get_count_at_value(units).unwrap());
+}
+
+#[test]
+fn reset() {
+    let mut h = micros_histogram();
+    h.record(Duration::from_millis(1)).unwrap();
+    h.reset();
+
+    assert_eq!(0, h.get_count());
+}
@@ -4,15 +4,18 @@ use hdr_histogram::simple_hdr_histogram::*;
 
 #[derive(Debug)]
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
 pub struct HistogramIterationValue<T: HistogramCount> {
     // TODO add tests for other fields we want to expose and mark public
     pub value_iterated_to: u64,
     value_iterated_from: u64,
     pub count_at_value_iterated_to: T,
     // many counts may be covered in one step, so use largest type
-    pub count_added_in_this_iteration_step: u64,
-    total_count_to_this_value: u64, // TODO Generify to allow for bigint?
-    total_value_to_this_value: u64, // TODO Generify to allow for bigint?
+    pub count_added_in_this_iteration_step: u128,
+    /// Total count of all values up to and including this one
+    pub total_count_to_this_value: u128,
+    /// Sum of all values up to and including this one, saturating at u128::max_value()
+    total_value_to_this_value: u128,
     /// The percentile at the current position
     pub percentile: f64,
     /// The percentile intended to iterate to. This can be different from percentile if, for
@@ -39,6 +42,12 @@ impl<T: HistogramCount> Default for HistogramIterationValue<T> {
 }
 
 impl<T: HistogramCount> HistogramIterationValue<T> {
+    /// The value iterated to, converted to the units of the values that were recorded. This is
+    /// the same as `value_iterated_to` except when iterating over a `DoubleHistogram`.
+    pub fn double_value_iterated_to(&self) -> f64 {
+        self.value_iterated_to as f64 * self.integer_to_double_value_conversion_ratio
+    }
+
     fn reset(&mut self) {
         *self = HistogramIterationValue { ..HistogramIterationValue::default() };
     }
@@ -46,9 +55,9 @@ impl<T: HistogramCount> HistogramIterationValue<T> {
             value_iterated_to: u64,
             value_iterated_from: u64,
             count_at_value_iterated_to: T,
-            count_added_in_this_iteration_step: u64,
-            total_count_to_this_value: u64,
-            total_value_to_this_value: u64,
+            count_added_in_this_iteration_step: u128,
+            total_count_to_this_value: u128,
+            total_value_to_this_value: u128,
             percentile: f64,
             percentile_level_iterated_to: f64,
             integer_to_double_value_conversion_ratio: f64) {
@@ -64,27 +73,27 @@ impl<T: HistogramCount> HistogramIterationValue<T> {
     }
 }
 
-impl<'a, T: HistogramCount> IntoIterator for RecordedValues<'a, T> {
+impl<'a, T: HistogramCount, C: Counts<T>> IntoIterator for RecordedValues<'a, T, C> {
     type Item = HistogramIterationValue<T>;
-    type IntoIter = BaseHistogramIterator<'a, T, RecordedValuesStrategy>;
+    type IntoIter = BaseHistogramIterator<'a, T, RecordedValuesStrategy, C>;
 
     fn into_iter(self) -> Self::IntoIter {
         BaseHistogramIterator::new(self.histo, RecordedValuesStrategy { visited_index: -1 })
     }
 }
 
-impl<'a, T: HistogramCount> IntoIterator for AllValues<'a, T> {
+impl<'a, T: HistogramCount, C: Counts<T>> IntoIterator for AllValues<'a, T, C> {
     type Item = HistogramIterationValue<T>;
-    type IntoIter = BaseHistogramIterator<'a, T, AllValuesStrategy>;
+    type IntoIter = BaseHistogramIterator<'a, T, AllValuesStrategy, C>;
 
     fn into_iter(self) -> Self::IntoIter {
         BaseHistogramIterator::new(self.histo, AllValuesStrategy { visited_index: -1 })
     }
 }
 
-impl<'a, T: HistogramCount> IntoIterator for LogarithmicValues<'a, T> {
+impl<'a, T: HistogramCount, C: Counts<T>> IntoIterator for LogarithmicValues<'a, T, C> {
     type Item = HistogramIterationValue<T>;
-    type IntoIter = BaseHistogramIterator<'a, T, LogarithmicValuesStrategy>;
+    type IntoIter = BaseHistogramIterator<'a, T, LogarithmicValuesStrategy, C>;
 
     fn into_iter(self) -> Self::IntoIter {
         let first_step_highest_value = self.value_units_in_first_bucket - 1;
@@ -98,9 +107,9 @@ impl<'a, T: HistogramCount> IntoIterator for LogarithmicValues<'a, T> {
     }
 }
 
-impl<'a, T: HistogramCount> IntoIterator for LinearValues<'a, T> {
+impl<'a, T: HistogramCount, C: Counts<T>> IntoIterator for LinearValues<'a, T, C> {
     type Item = HistogramIterationValue<T>;
-    type IntoIter = BaseHistogramIterator<'a, T, LinearValuesStrategy>;
+    type IntoIter = BaseHistogramIterator<'a, T, LinearValuesStrategy, C>;
 
     fn into_iter(self) -> Self::IntoIter {
         let first_step_highest_value = self.value_units_per_bucket - 1;
@@ -113,9 +122,9 @@ impl<'a, T: HistogramCount> IntoIterator for LinearValues<'a, T> {
     }
 }
 
-impl<'a, T: HistogramCount> IntoIterator for Percentiles<'a, T> {
+impl<'a, T: HistogramCount, C: Counts<T>> IntoIterator for Percentiles<'a, T, C> {
     type Item = HistogramIterationValue<T>;
-    type IntoIter = BaseHistogramIterator<'a, T, PercentilesStrategy>;
+    type IntoIter = BaseHistogramIterator<'a, T, PercentilesStrategy, C>;
 
     fn into_iter(self) -> Self::IntoIter {
         BaseHistogramIterator::new(self.histo, PercentilesStrategy {
@@ -133,19 +142,19 @@ pub struct RecordedValuesStrategy {
     visited_index: i32
 }
 
-impl<'a, T: HistogramCount + 'a> IterationStrategy<'a, T> for RecordedValuesStrategy {
+impl<'a, T: HistogramCount + 'a, C: Counts<T> + 'a> IterationStrategy<'a, T, C>
+        for RecordedValuesStrategy {
 
-    fn increment_iteration_level(&mut self, iter: &BaseHistogramIterator<'a, T, Self>) {
+    fn increment_iteration_level(&mut self, iter: &BaseHistogramIterator<'a, T, Self, C>) {
         // cast is safe; count indexes << 2^32
         self.visited_index = iter.current_index as i32;
     }
 
-    fn reached_iteration_level(&self, iter: &BaseHistogramIterator<'a, T, Self>) -> bool {
+    fn reached_iteration_level(&self, iter: &BaseHistogramIterator<'a, T, Self, C>) -> bool {
         let current_count: T = match iter.histogram.get_count_at_index(iter.current_index) {
-            Err(err) => {
-                println!("error: {}", err);
-                T::zero()}, // TODO
-            Ok(the_count) => the_count
+            Ok(the_count) => the_count,
+            // nothing can be recorded outside the counts array
+            Err(QueryError::IndexOutOfRange) => T::zero()
         };
         // detects when we enter the main iteration loop for the first time after having previously
         // returned out of the loop
@@ -163,18 +172,19 @@ pub struct AllValuesStrategy {
     visited_index: i32
 }
 
-impl<'a, T: HistogramCount + 'a> IterationStrategy<'a, T> for AllValuesStrategy {
+impl<'a, T: HistogramCount + 'a, C: Counts<T> + 'a> IterationStrategy<'a, T, C>
+        for AllValuesStrategy {
 
-    fn increment_iteration_level(&mut self, iter: &BaseHistogramIterator<'a, T, Self>) {
+    fn increment_iteration_level(&mut self, iter: &BaseHistogramIterator<'a, T, Self, C>) {
         // cast is safe; count indexes << 2^32
         self.visited_index = iter.current_index as i32;
     }
 
-    fn reached_iteration_level(&self, iter: &BaseHistogramIterator<'a, T, Self>) -> bool {
+    fn reached_iteration_level(&self, iter: &BaseHistogramIterator<'a, T, Self, C>) -> bool {
         self.visited_index != iter.current_index as i32
     }
 
-    fn allow_further_iteration(&mut self, iter: &BaseHistogramIterator<'a, T, Self>) -> bool {
+    fn allow_further_iteration(&mut self, iter: &BaseHistogramIterator<'a, T, Self, C>) -> bool {
         // Unlike other iterators AllValues is only done when we've exhausted the indices:
         iter.current_index < (iter.histogram.counts.len() - 1)
     }
@@ -194,16 +204,17 @@ pub struct LogarithmicValuesStrategy {
     current_step_lowest_value_reporting_level: u64
 }
 
-impl<'a, T: HistogramCount + 'a> IterationStrategy<'a, T> for LogarithmicValuesStrategy {
+impl<'a, T: HistogramCount + 'a, C: Counts<T> + 'a> IterationStrategy<'a, T, C>
+        for LogarithmicValuesStrategy {
 
-    fn increment_iteration_level(&mut self, iter: &BaseHistogramIterator<'a, T, Self>) {
+    fn increment_iteration_level(&mut self, iter: &BaseHistogramIterator<'a, T, Self, C>) {
         self.next_value_reporting_level *= self.log_base;
         self.current_step_highest_value_reporting_level = self.next_value_reporting_level - 1;
         self.current_step_lowest_value_reporting_level =
             iter.histogram.lowest_equivalent_value(self.current_step_highest_value_reporting_level);
     }
 
-    fn reached_iteration_level(&self, iter: &BaseHistogramIterator<'a, T, Self>) -> bool {
+    fn reached_iteration_level(&self, iter: &BaseHistogramIterator<'a, T, Self, C>) -> bool {
         // emit current position if the value fits within the current reporting range or we've
         // reached the last element while looking for a big enough value. This last would happen
         // if no values are big enough to reach the next log bucket.
@@ -211,7 +222,7 @@ impl<'a, T: HistogramCount + 'a> IterationStrategy<'a, T> for LogarithmicValuesS
             || (iter.current_index >= iter.histogram.counts.len() - 1)
     }
 
-    fn allow_further_iteration(&mut self, iter: &BaseHistogramIterator<'a, T, Self>) -> bool {
+    fn allow_further_iteration(&mut self, iter: &BaseHistogramIterator<'a, T, Self, C>) -> bool {
         if self._default_allow_further_iteration(iter) {
             return true;
         }
@@ -224,7 +235,7 @@ impl<'a, T: HistogramCount + 'a> IterationStrategy<'a, T> for LogarithmicValuesS
             < iter.next_value_at_index
     }
 
-    fn value_iterated_to(&self, _: &BaseHistogramIterator<'a, T, Self>) -> u64 {
+    fn value_iterated_to(&self, _: &BaseHistogramIterator<'a, T, Self, C>) -> u64 {
         self.current_step_highest_value_reporting_level
     }
 
@@ -244,15 +255,16 @@ pub struct LinearValuesStrategy {
     current_step_lowest_value_reporting_level: u64
 }
 
-impl<'a, T: HistogramCount + 'a> IterationStrategy<'a, T> for LinearValuesStrategy {
+impl<'a, T: HistogramCount + 'a, C: Counts<T> + 'a> IterationStrategy<'a, T, C>
+        for LinearValuesStrategy {
 
-    fn increment_iteration_level(&mut self, iter: &BaseHistogramIterator<'a, T, Self>) {
+    fn increment_iteration_level(&mut self, iter: &BaseHistogramIterator<'a, T, Self, C>) {
         self.current_step_highest_value_reporting_level += self.value_units_per_bucket;
         self.current_step_lowest_value_reporting_level =
             iter.histogram.lowest_equivalent_value(self.current_step_highest_value_reporting_level);
     }
 
-    fn reached_iteration_level(&self, iter: &BaseHistogramIterator<'a, T, Self>) -> bool {
+    fn reached_iteration_level(&self, iter: &BaseHistogramIterator<'a, T, Self, C>) -> bool {
         // emit current position if the value fits within the current reporting range or we've
         // reached the last element while looking for a big enough value. This last would happen
         // if no values are big enough to reach the next log bucket.
@@ -260,7 +272,7 @@ impl<'a, T: HistogramCount + 'a> IterationStrategy<'a, T> for LinearValuesStrate
             || (iter.current_index >= iter.histogram.counts.len() - 1)
     }
 
-    fn allow_further_iteration(&mut self, iter: &BaseHistogramIterator<'a, T, Self>) -> bool {
+    fn allow_further_iteration(&mut self, iter: &BaseHistogramIterator<'a, T, Self, C>) -> bool {
         if self._default_allow_further_iteration(iter) {
             return true;
         }
@@ -272,7 +284,7 @@ impl<'a, T: HistogramCount + 'a> IterationStrategy<'a, T> for LinearValuesStrate
         self.current_step_highest_value_reporting_level + 1 < iter.next_value_at_index
     }
 
-    fn value_iterated_to(&self, _: &BaseHistogramIterator<'a, T, Self>) -> u64 {
+    fn value_iterated_to(&self, _: &BaseHistogramIterator<'a, T, Self, C>) -> u64 {
         self.current_step_highest_value_reporting_level
     }
 
@@ -292,9 +304,10 @@ pub struct PercentilesStrategy {
     reached_last_recorded_value: bool
 }
 
-impl<'a, T: HistogramCount + 'a> IterationStrategy<'a, T> for PercentilesStrategy {
+impl<'a, T: HistogramCount + 'a, C: Counts<T> + 'a> IterationStrategy<'a, T, C>
+        for PercentilesStrategy {
 
-    fn increment_iteration_level(&mut self, _: &BaseHistogramIterator<'a, T, Self>) {
+    fn increment_iteration_level(&mut self, _: &BaseHistogramIterator<'a, T, Self, C>) {
         self.percentile_level_to_iterate_from = self.percentile_level_to_iterate_to;
 
         // To calculate the delta to add on at the current iteration, we want to know how many
@@ -314,15 +327,17 @@ impl<'a, T: HistogramCount + 'a> IterationStrategy<'a, T> for PercentilesStrateg
         // number of times the remaining percentile distance would fit into 100.0
         let multiples_of_remaining_distance: f64 = 100.0/(100.0 - self.percentile_level_to_iterate_to);
         // 2x the largest power of 2 that's smaller than the number above (the + 1 power of 2
-        // handles the doubling needed because we have ticks per *half*)
-        let multiples_pwr2: u32 = 2_u32.pow((multiples_of_remaining_distance.ln() / 2_f64.ln()) as u32 + 1);
+        // handles the doubling needed because we have ticks per *half*). This is done in f64
+        // because at 100% the remaining distance is 0, so the multiples are infinite, and close
+        // to 100% the ticks would overflow an integer anyway.
+        let multiples_pwr2: f64 = 2_f64.powf(multiples_of_remaining_distance.log2().floor() + 1.0);
         // total number of ticks
-        let pctile_ticks: u32 = self.percentile_ticks_per_half_distance * multiples_pwr2;
+        let pctile_ticks: f64 = self.percentile_ticks_per_half_distance as f64 * multiples_pwr2;
         // add on the per-tick delta
-        self.percentile_level_to_iterate_to += 100.0 / pctile_ticks as f64;
+        self.percentile_level_to_iterate_to += 100.0 / pctile_ticks;
     }
 
-    fn reached_iteration_level(&self, iter: &BaseHistogramIterator<'a, T, Self>) -> bool {
+    fn reached_iteration_level(&self, iter: &BaseHistogramIterator<'a, T, Self, C>) -> bool {
         if iter.count_at_this_value == T::zero() {
             return false;
         }
@@ -331,7 +346,7 @@ impl<'a, T: HistogramCount + 'a> IterationStrategy<'a, T> for PercentilesStrateg
         current_percentile >= self.percentile_level_to_iterate_to
     }
 
-    fn allow_further_iteration(&mut self, iter: &BaseHistogramIterator<'a, T, Self>) -> bool {
+    fn allow_further_iteration(&mut self, iter: &BaseHistogramIterator<'a, T, Self, C>) -> bool {
         if self._default_allow_further_iteration(iter) {
             return true;
         }
@@ -346,7 +361,7 @@ impl<'a, T: HistogramCount + 'a> IterationStrategy<'a, T> for PercentilesStrateg
         return false;
     }
 
-    fn percentile_iterated_to(&self, iter: &BaseHistogramIterator<'a, T, Self>) -> f64 {
+    fn percentile_iterated_to(&self, iter: &BaseHistogramIterator<'a, T, Self, C>) -> f64 {
         self.percentile_level_to_iterate_to
     }
 
@@ -361,15 +376,16 @@ impl<'a, T: HistogramCount + 'a> IterationStrategy<'a, T> for PercentilesStrateg
 }
 
 // this is really a recorded value iterator mashed together with its base class
-impl<'a, T: HistogramCount + 'a, S: IterationStrategy<'a, T>> BaseHistogramIterator<'a, T, S> {
+impl<'a, T: HistogramCount + 'a, S: IterationStrategy<'a, T, C>, C: Counts<T> + 'a>
+        BaseHistogramIterator<'a, T, S, C> {
 
-    fn new(histo: &'a SimpleHdrHistogram<T>, strategy: S) -> BaseHistogramIterator<'a, T, S> {
+    fn new(histo: &'a SimpleHdrHistogram<T, C>, strategy: S) -> BaseHistogramIterator<'a, T, S, C> {
         BaseHistogramIterator {
             histogram: histo,
             strategy: strategy,
             saved_histogram_total_raw_count: histo.get_count(),
             array_total_count: histo.get_count(),
-            integer_to_double_value_conversion_ratio: 0.0, //TODO should be histogram.integer_to_double_value_conversion_ratio
+            integer_to_double_value_conversion_ratio: histo.integer_to_double_value_conversion_ratio,
             current_index: 0,
             current_value_at_index: 0,
             next_value_at_index: 1 << histo.get_unit_magnitude(),
@@ -384,11 +400,12 @@ impl<'a, T: HistogramCount + 'a, S: IterationStrategy<'a, T>> BaseHistogramItera
         }
     }
 
-    fn reset_iterator(&mut self, histogram: &'a SimpleHdrHistogram<T>) {
+    fn reset_iterator(&mut self, histogram: &'a SimpleHdrHistogram<T, C>) {
         self.histogram = histogram;
         self.saved_histogram_total_raw_count = self.histogram.get_count();
         self.array_total_count = self.histogram.get_count();
-        self.integer_to_double_value_conversion_ratio = 0.0; //TODO should be self.histogram.integer_to_double_value_conversion_ratio
+        self.integer_to_double_value_conversion_ratio =
+            self.histogram.integer_to_double_value_conversion_ratio;
         self.current_index = 0;
         self.current_value_at_index = 0;
         self.next_value_at_index = 1 << self.histogram.get_unit_magnitude();
@@ -418,7 +435,8 @@ impl<'a, T: HistogramCount + 'a, S: IterationStrategy<'a, T>> BaseHistogramItera
 
 }
 
-impl<'a, T: HistogramCount + 'a, S: IterationStrategy<'a, T>> Iterator for BaseHistogramIterator<'a, T, S> {
+impl<'a, T: HistogramCount + 'a, S: IterationStrategy<'a, T, C>, C: Counts<T> + 'a> Iterator
+        for BaseHistogramIterator<'a, T, S, C> {
     // TODO should this be a ref?
     type Item = HistogramIterationValue<T>;
 
@@ -438,14 +456,18 @@ impl<'a, T: HistogramCount + 'a, S: IterationStrategy<'a, T>> Iterator for BaseH
         while ! self.exhausted_sub_buckets() {
             self.count_at_this_value = match self.histogram.get_count_at_index(self.current_index) {
                 Ok(val) => val,
-                Err(err) => T::one() + T::one() + T::one() // TODO handle error
+                // can't happen since we've checked that sub buckets aren't exhausted, but if it
+                // did, there would be nothing counted there
+                Err(QueryError::IndexOutOfRange) => T::zero()
             };
             if self.fresh_sub_bucket {
-                // all count types can become u64
-                let count_u64 = self.count_at_this_value.to_u64().unwrap();
-                self.total_count_to_current_index += count_u64;
+                // all count types can become u128
+                let count_u128 = self.count_at_this_value.to_u128().unwrap();
+                self.total_count_to_current_index += count_u128;
                 let highest_eq_val = self.histogram.highest_equivalent_value(self.current_value_at_index);
-                self.total_value_to_current_index += count_u64 * highest_eq_val;
+                // this can only overflow with enormous u128 counts, and it's not worth failing over
+                self.total_value_to_current_index = self.total_value_to_current_index
+                    .saturating_add(count_u128.saturating_mul(highest_eq_val as u128));
                 self.fresh_sub_bucket = false;
             }
 
@@ -333,6 +333,20 @@ fn percentiles_matches_histo_get_value_at_pctile() {
     }
 }
 
+#[test]
+fn recorded_values_totals_beyond_u64() {
+    let mut h = histo64(1, 1 << 50, 3);
+    h.record_value_with_count(1000, u64::max_value()).unwrap();
+    h.record_value_with_count(1 << 40, u64::max_value()).unwrap();
+
+    let values: Vec<HistogramIterationValue<u64>> = h.recorded_values().into_iter().collect();
+
+    assert_eq!(2, values.len());
+    assert_eq!(u64::max_value() as u128, values[0].total_count_to_this_value);
+    assert_eq!(u64::max_value() as u128 * 2, values[1].total_count_to_this_value);
+    assert_eq!(100.0, values[1].percentile);
+}
+
 
 #[cfg(test)]
 fn prepare_histo_for_logarithmic_iterator() -> SimpleHdrHistogram<u64> {
@@ -1,28 +1,115 @@
 use std::cmp;
 use std::cmp::Ord;
+use std::iter::Sum;
+use std::marker::PhantomData;
+use std::ops::AddAssign;
+use std::ops::SubAssign;
 use num::traits::Zero;
 use num::traits::One;
+use num::traits::Bounded;
 use num::traits::ToPrimitive;
+use num::traits::CheckedAdd;
+use num::traits::CheckedSub;
+use num::traits::FromPrimitive;
 
+use hdr_histogram::errors::*;
 use hdr_histogram::simple_hdr_histogram::iterator::*;
 
+pub use hdr_histogram::simple_hdr_histogram::atomic::AtomicHistogram;
+pub use hdr_histogram::simple_hdr_histogram::builder::HistogramBuilder;
+pub use hdr_histogram::simple_hdr_histogram::counts::AutoWideningCounts;
+pub use hdr_histogram::simple_hdr_histogram::counts::AutoWideningHistogram;
+pub use hdr_histogram::simple_hdr_histogram::counts::Counts;
+pub use hdr_histogram::simple_hdr_histogram::counts::PackedCounts;
+pub use hdr_histogram::simple_hdr_histogram::counts::PackedHistogram;
+pub use hdr_histogram::simple_hdr_histogram::double::DoubleHistogram;
+pub use hdr_histogram::simple_hdr_histogram::duration::DurationHistogram;
+pub use hdr_histogram::simple_hdr_histogram::duration::DurationTimer;
+pub use hdr_histogram::simple_hdr_histogram::duration::DurationUnit;
+pub use hdr_histogram::simple_hdr_histogram::output::PercentileOutputFormat;
+pub use hdr_histogram::simple_hdr_histogram::phaser::PhaserReaderGuard;
+pub use hdr_histogram::simple_hdr_histogram::phaser::WriterCriticalSection;
+pub use hdr_histogram::simple_hdr_histogram::phaser::WriterReaderPhaser;
+pub use hdr_histogram::simple_hdr_histogram::recorder::Recorder;
+pub use hdr_histogram::simple_hdr_histogram::serialization::MAX_COMPRESSION_LEVEL;
+
+mod atomic;
+mod builder;
+mod counts;
+mod double;
+mod duration;
 mod iterator;
+mod output;
+mod phaser;
+mod recorder;
+mod serialization;
+mod shift;
+#[cfg(feature = "serde")] mod serde_support;
+#[cfg(test)] mod atomic_test;
+#[cfg(test)] mod builder_test;
+#[cfg(test)] mod counts_test;
+#[cfg(test)] mod double_test;
+#[cfg(test)] mod duration_test;
 #[cfg(test)] mod iterator_test;
+#[cfg(test)] mod output_test;
+#[cfg(test)] mod phaser_test;
+#[cfg(test)] mod recorder_test;
+#[cfg(test)] mod serialization_test;
+#[cfg(test)] mod shift_test;
+#[cfg(all(test, feature = "serde"))] mod serde_support_test;
 #[cfg(test)] mod test;
 
-/// Marker trait for types we allow (namely, u8-u64)
-pub trait HistogramCount : Ord + Zero + One + ToPrimitive + Copy {}
+/// Marker trait for types we allow (namely, u8-u128)
+pub trait HistogramCount : Ord + Zero + One + Bounded + ToPrimitive + FromPrimitive + CheckedAdd
+    + CheckedSub + Copy {}
 
 impl HistogramCount for u8 {}
 impl HistogramCount for u16 {}
 impl HistogramCount for u32 {}
 impl HistogramCount for u64 {}
+impl HistogramCount for u128 {}
+
+/// What to do with a value beyond the highest trackable value when the histogram isn't
+/// auto-resizing.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum OutOfRangePolicy {
+    /// Fail with `RecordError::ValueOutOfRange`. This is the default.
+    Error,
+    /// Record it in the top bucket as if it were the largest value the histogram can hold.
+    Clamp,
+    /// Discard it, adding its count to the out of range count instead.
+    Drop,
+}
+
+/// What to do when recording would push the count at a value beyond what the count type can hold.
+/// This matters mostly for small count types like u8 and u16.
+///
+/// A histogram's count type is fixed, so there is no policy to widen it. For counts that start
+/// small and widen as needed, use an `AutoWideningHistogram`, whose `AutoWideningCounts` storage
+/// goes from u8 up to u64.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum CountOverflowPolicy {
+    /// Fail with `RecordError::CountOverflow` (or `AdditionError::CountOverflow` when adding
+    /// histograms) without recording anything. This is the default.
+    Error,
+    /// Stop at the count type's max value, adding whatever didn't fit to the saturated count.
+    /// Recording still returns `Ok(())`, so saturation only shows up in
+    /// `SimpleHdrHistogram::get_saturated_count`.
+    Saturate,
+}
 
 ///
 /// This struct essentially encapsulates the "instance variables" of the histogram
 ///
 #[derive(Debug)]
-pub struct SimpleHdrHistogram<T:HistogramCount> {
+pub struct SimpleHdrHistogram<T:HistogramCount, C: Counts<T> = Vec<T>> {
+    /// Configuration as provided to the constructor, kept so that identically configured
+    /// histograms can be created from this one.
+    lowest_discernible_value: u64,
+    highest_trackable_value: u64,
+    num_significant_digits: u32,
     /// Number of leading zeros in the largest value that can fit in bucket 0.
     leading_zeros_count_base: usize,
     /// Biggest value that can fit in bucket 0
@@ -32,24 +119,58 @@ pub struct SimpleHdrHistogram<T:HistogramCount> {
     // always at least 1
     sub_bucket_half_count: usize,
     sub_bucket_half_count_magnitude: u32,
-    counts: Vec<T>,
+    counts: C,
+    /// `counts` holds `T`s, but only via `C`.
+    count_type: PhantomData<T>,
     // is at most counts.len(), so i32 is plenty since counts scales exponentially
     /// Index offset (used to express left/right shifts of values)
     normalizing_index_offset: i32,
     max_value: u64,
     min_non_zero_value: u64,
     unit_magnitude_mask: u64,
-    total_count: u64,
+    /// u128 so that it can't overflow even when individual counts are large.
+    total_count: u128,
+    /// If true, recording a value beyond the highest trackable value grows `counts` instead of
+    /// failing.
+    auto_resize: bool,
+    out_of_range_policy: OutOfRangePolicy,
+    /// Number of values discarded by `OutOfRangePolicy::Drop`. Not included in `total_count`.
+    out_of_range_count: u128,
+    count_overflow_policy: CountOverflowPolicy,
+    /// Number of values discarded by `CountOverflowPolicy::Saturate`. Not included in
+    /// `total_count`.
+    saturated_count: u128,
+    /// Multiplier from recorded integer values to the values they represent. 1.0 unless set with
+    /// `HistogramBuilder::value_unit_ratio`, or managed by a `DoubleHistogram`.
+    integer_to_double_value_conversion_ratio: f64,
 }
 
-pub trait HistogramBase<T: HistogramCount> {
-    // TODO error handling improvements
-    fn record_single_value(&mut self, value: u64) -> Result<(), String>;
+pub trait HistogramBase<T: HistogramCount, C: Counts<T> = Vec<T>> {
+    /// Record `value` once. Count overflow is handled as per `record_value_with_count`.
+    fn record_single_value(&mut self, value: u64) -> Result<(), RecordError>;
+    /// Record `count` occurrences of `value` in one step. If the count at that value would
+    /// overflow the count type, what happens depends on the `CountOverflowPolicy`: by default it
+    /// fails without modifying the histogram. With `CountOverflowPolicy::Saturate`, saturating
+    /// is not reported in the returned `Result`; check `get_saturated_count` instead.
+    fn record_value_with_count(&mut self, value: u64, count: T) -> Result<(), RecordError>;
+    /// Record a value, correcting for coordinated omission: if `value` is larger than
+    /// `expected_interval`, the samples that would have been recorded every `expected_interval`
+    /// while waiting for this one (value - interval, value - 2 * interval, ...) are back-filled.
+    /// An `expected_interval` of 0 disables correction.
+    ///
+    /// Use this for recording in real time; to correct after the fact, see
+    /// `copy_corrected_for_coordinated_omission`. Count overflow is handled as per
+    /// `record_value_with_count`.
+    fn record_value_with_expected_interval(&mut self, value: u64, expected_interval: u64)
+        -> Result<(), RecordError>;
 
     /// Returns the number of values stored in this histo
-    fn get_count(&self) -> u64;
+    fn get_count(&self) -> u128;
+    /// Returns the number of values that were discarded because they were out of range (see
+    /// `OutOfRangePolicy::Drop`). These are not included in `get_count`.
+    fn get_out_of_range_count(&self) -> u128;
     /// Returns the count at the specified value (as well as other equivalent values)
-    fn get_count_at_value(&self, value: u64) -> Result<T, String>;
+    fn get_count_at_value(&self, value: u64) -> Result<T, QueryError>;
 
     /// Returns the max value stored. Undefined if no values have been stored.
     fn get_max(&self) -> u64;
@@ -64,6 +185,22 @@ pub trait HistogramBase<T: HistogramCount> {
     /// > 0.0, returns the value that the given percentage of the overall recorded value entries
     /// in the histogram are either smaller than or equivalent to.
     fn get_value_at_percentile(&self, percentile: f64) -> u64;
+    /// Returns the percentage of recorded values that are less than or equivalent to the
+    /// provided value. This is the inverse of `get_value_at_percentile`. Returns 100.0 if no
+    /// values have been recorded.
+    fn get_percentile_below(&self, value: u64) -> f64;
+    /// Returns the number of recorded values between low and high, inclusive, where each bound
+    /// includes the values equivalent to it.
+    fn get_count_between(&self, low: u64, high: u64) -> u128;
+
+    /// Returns the mean of the recorded values, using the median equivalent value of each.
+    /// Returns 0.0 if no values have been recorded.
+    fn mean(&self) -> f64;
+    /// Returns the standard deviation of the recorded values, using the median equivalent value
+    /// of each. Returns 0.0 if no values have been recorded.
+    fn stdev(&self) -> f64;
+    /// Returns the value at the 50th percentile.
+    fn median(&self) -> u64;
 
     /// Returns the lowest value equivalent to the provided value (equivalent meaning will store
     /// counts in the same memory location)
@@ -75,23 +212,26 @@ pub trait HistogramBase<T: HistogramCount> {
     fn next_non_equivalent_value(&self, value: u64) -> u64;
     /// Returns the number of distinct values that will map to the same count as the provided value
     fn size_of_equivalent_value_range(&self, value: u64) -> u64;
+    /// Returns the value in the middle (rounded up) of the range of values equivalent to the
+    /// provided value
+    fn median_equivalent_value(&self, value: u64) -> u64;
 
     /// Iterate across all recorded values
-    fn recorded_values(&self) -> RecordedValues<T>;
+    fn recorded_values(&self) -> RecordedValues<T, C>;
     /// Iterate across all expressible values, recorded or not
-    fn all_values(&self) -> AllValues<T>;
+    fn all_values(&self) -> AllValues<T, C>;
     /// Iterate across exponentially increasing buckets, starting at value_units_in_first_bucket
     /// and increasing by log_base each step until recorded values are exhausted.
     fn logarithmic_bucket_values(&self, value_units_in_first_bucket: u64, log_base: u64)
-        -> LogarithmicValues<T>;
+        -> LogarithmicValues<T, C>;
     /// Iterate across equal-sized buckets until all recorded values are exhausted.
-    fn linear_bucket_values(&self, value_units_per_bucket: u64) -> LinearValues<T>;
+    fn linear_bucket_values(&self, value_units_per_bucket: u64) -> LinearValues<T, C>;
     /// Iterate across percentiles until all recorded values are exhausted.
-    fn percentiles(&self, percentile_ticks_per_half_distance: u32) -> Percentiles<T>;
+    fn percentiles(&self, percentile_ticks_per_half_distance: u32) -> Percentiles<T, C>;
 
 }
 
-impl<T: HistogramCount> HistogramBase<T> for SimpleHdrHistogram<T> {
+impl<T: HistogramCount, C: Counts<T>> HistogramBase<T, C> for SimpleHdrHistogram<T, C> {
 
     fn next_non_equivalent_value(&self, value: u64) -> u64 {
         self.lowest_equivalent_value(value) + self.size_of_equivalent_value_range(value)
@@ -102,14 +242,16 @@ impl<T: HistogramCount> HistogramBase<T> for SimpleHdrHistogram<T> {
         let sub_bucket_index = self.get_sub_bucket_index(value, bucket_index);
         // TODO when is sub_bucket_index >= sub_bucket_count
         let distance_to_next_value =
-            1 << (self.unit_magnitude
+            1_u64 << (self.unit_magnitude
                     + bucket_index as u32
                     + if sub_bucket_index >= self.sub_bucket_count {1} else {0});
-        distance_to_next_value as u64
+        distance_to_next_value
     }
 
     fn highest_equivalent_value(&self, value: u64) -> u64 {
-        self.next_non_equivalent_value(value) - 1
+        // equivalent to next_non_equivalent_value - 1, but doesn't overflow in the top bucket of a
+        // histogram that covers all of u64
+        self.lowest_equivalent_value(value) + (self.size_of_equivalent_value_range(value) - 1)
     }
 
     fn lowest_equivalent_value(&self, value: u64) -> u64 {
@@ -118,18 +260,22 @@ impl<T: HistogramCount> HistogramBase<T> for SimpleHdrHistogram<T> {
         self.value_from_index_sub(bucket_index, sub_bucket_index)
     }
 
+    fn median_equivalent_value(&self, value: u64) -> u64 {
+        self.lowest_equivalent_value(value) + (self.size_of_equivalent_value_range(value) >> 1)
+    }
+
     fn get_value_at_percentile(&self, percentile: f64) -> u64 {
         let requested_percentile = percentile.min(100.0);
         let mut count_at_percentile =
-            (((requested_percentile / 100.0) * self.get_count() as f64) + 0.5) as u64;
+            (((requested_percentile / 100.0) * self.get_count() as f64) + 0.5) as u128;
         count_at_percentile = cmp::max(count_at_percentile, 1);
-        let mut total_to_current_index: u64 = 0;
+        let mut total_to_current_index: u128 = 0;
         for i in 0..self.counts.len() {
             let count_at_index = self.get_count_at_index(i as usize);
             match count_at_index {
                 Ok(count) => {
-                    // we only use u8 - u64 types, so this must always work
-                    total_to_current_index += count.to_u64().unwrap();
+                    // we only use u8 - u128 types, so this must always work
+                    total_to_current_index += count.to_u128().unwrap();
                     if total_to_current_index >= count_at_percentile {
                         let value_at_index = self.value_from_index(i as usize);
                         return if percentile == 0.0 {
@@ -145,7 +291,52 @@ impl<T: HistogramCount> HistogramBase<T> for SimpleHdrHistogram<T> {
         0
     }
 
-    fn get_count_at_value(&self, value: u64) -> Result<T, String> {
+    fn get_percentile_below(&self, value: u64) -> f64 {
+        if self.total_count == 0 {
+            return 100.0;
+        }
+        let target_index = cmp::min(self.counts_array_index(value), self.counts.len() - 1);
+        let total_to_target_index = self.count_between_indexes(0, target_index);
+        (100.0 * total_to_target_index as f64) / self.total_count as f64
+    }
+
+    fn get_count_between(&self, low: u64, high: u64) -> u128 {
+        let low_index = self.counts_array_index(low);
+        let high_index = cmp::min(self.counts_array_index(high), self.counts.len() - 1);
+        self.count_between_indexes(low_index, high_index)
+    }
+
+    fn mean(&self) -> f64 {
+        if self.total_count == 0 {
+            return 0.0;
+        }
+        let mut total_value = 0.0;
+        for v in self.recorded_values() {
+            total_value += self.median_equivalent_value(v.value_iterated_to) as f64
+                * v.count_at_value_iterated_to.to_f64().unwrap();
+        }
+        total_value / self.total_count as f64
+    }
+
+    fn stdev(&self) -> f64 {
+        if self.total_count == 0 {
+            return 0.0;
+        }
+        let mean = self.mean();
+        let mut geometric_deviation_total = 0.0;
+        for v in self.recorded_values() {
+            let deviation = self.median_equivalent_value(v.value_iterated_to) as f64 - mean;
+            geometric_deviation_total += deviation * deviation
+                * v.count_at_value_iterated_to.to_f64().unwrap();
+        }
+        (geometric_deviation_total / self.total_count as f64).sqrt()
+    }
+
+    fn median(&self) -> u64 {
+        self.get_value_at_percentile(50.0)
+    }
+
+    fn get_count_at_value(&self, value: u64) -> Result<T, QueryError> {
         // TODO is it ok to just clamp to max value rathe than saying it's inexpressible?
         let index = cmp::min(cmp::max(0, self.counts_array_index(value)), self.counts.len() - 1);
         self.get_count_at_index(index)
@@ -159,42 +350,54 @@ impl<T: HistogramCount> HistogramBase<T> for SimpleHdrHistogram<T> {
         self.min_non_zero_value
     }
 
-    fn get_count(&self) -> u64 {
+    fn get_count(&self) -> u128 {
         self.total_count
     }
 
+    fn get_out_of_range_count(&self) -> u128 {
+        self.out_of_range_count
+    }
+
     fn get_unit_magnitude(&self) -> u32 {
         self.unit_magnitude
     }
 
-    fn record_single_value(&mut self, value: u64) -> Result<(), String> {
-        let counts_index = self.counts_array_index(value);
-            match self.increment_count_at_index(counts_index) {
-                Ok(_) => {
-                    self.update_min_and_max(value);
-                    self.increment_total_count();
-                    Ok(())
-                }
-                Err(err) => {
-                    Err(String::from(format!("Could not increment count at index due to: {}", err)))
-                }
-            }
+    fn record_single_value(&mut self, value: u64) -> Result<(), RecordError> {
+        self.record_value_with_count(value, T::one())
+    }
+
+    fn record_value_with_count(&mut self, value: u64, count: T) -> Result<(), RecordError> {
+        // we only use u8 - u128 types, so this must always work
+        let count_u128 = count.to_u128().unwrap();
+        let (counts_index, value) = match self.index_to_record_at(value, count_u128)? {
+            Some(index_and_value) => index_and_value,
+            None => return Ok(())
+        };
+        let added = self.add_to_count_at_index(counts_index, count)?;
+        self.update_min_and_max(value);
+        self.add_to_total_count(added);
+        Ok(())
+    }
+
+    fn record_value_with_expected_interval(&mut self, value: u64, expected_interval: u64)
+            -> Result<(), RecordError> {
+        self.record_value_with_count_and_expected_interval(value, T::one(), expected_interval)
     }
 
-    fn recorded_values(&self) -> RecordedValues<T> {
+    fn recorded_values(&self) -> RecordedValues<T, C> {
         RecordedValues {
             histo: self
         }
     }
 
-    fn all_values(&self) -> AllValues<T> {
+    fn all_values(&self) -> AllValues<T, C> {
         AllValues {
             histo: self
         }
     }
 
     fn logarithmic_bucket_values(&self, value_units_in_first_bucket: u64, log_base: u64)
-            -> LogarithmicValues<T> {
+            -> LogarithmicValues<T, C> {
         LogarithmicValues {
             histo: self,
             value_units_in_first_bucket: value_units_in_first_bucket,
@@ -202,14 +405,14 @@ impl<T: HistogramCount> HistogramBase<T> for SimpleHdrHistogram<T> {
         }
     }
 
-    fn linear_bucket_values(&self, value_units_per_bucket: u64) -> LinearValues<T> {
+    fn linear_bucket_values(&self, value_units_per_bucket: u64) -> LinearValues<T, C> {
         LinearValues {
             histo: self,
             value_units_per_bucket: value_units_per_bucket
         }
     }
 
-    fn percentiles(&self, percentile_ticks_per_half_distance: u32) -> Percentiles<T> {
+    fn percentiles(&self, percentile_ticks_per_half_distance: u32) -> Percentiles<T, C> {
         Percentiles {
             histo: self,
             percentile_ticks_per_half_distance: percentile_ticks_per_half_distance
@@ -223,16 +426,59 @@ impl<T: HistogramCount> SimpleHdrHistogram<T> {
     /// lowest_discernible_value: must be >= 1
     /// highest_trackable_value: must be >= 2 * lowest_discernible_value
     /// num_significant_digits: must be <= 5
+    ///
+    /// Panics if the configuration is invalid; see `try_new` for a non-panicking alternative.
     pub fn new(lowest_discernible_value: u64, highest_trackable_value: u64, num_significant_digits: u32) -> SimpleHdrHistogram<T> {
+        match SimpleHdrHistogram::try_new(lowest_discernible_value, highest_trackable_value,
+                                          num_significant_digits) {
+            Ok(h) => h,
+            Err(err) => panic!("Invalid histogram configuration: {}", err)
+        }
+    }
 
-        assert!(lowest_discernible_value >= 1);
-        assert!(highest_trackable_value >= 2 * lowest_discernible_value);
-        assert!(num_significant_digits <= 5);
+    /// Like `new`, but returns an error describing what's wrong with an invalid configuration
+    /// instead of panicking.
+    pub fn try_new(lowest_discernible_value: u64, highest_trackable_value: u64, num_significant_digits: u32)
+            -> Result<SimpleHdrHistogram<T>, CreationError> {
+        SimpleHdrHistogram::try_new_with_counts(lowest_discernible_value, highest_trackable_value,
+                                                num_significant_digits)
+    }
+}
+
+impl<T: HistogramCount, C: Counts<T>> SimpleHdrHistogram<T, C> {
+
+    /// Like `new`, but storing counts in `C` rather than a `Vec<T>`, e.g.
+    /// `AutoWideningHistogram::new_with_counts(1, 3600 * 1000 * 1000, 3)`.
+    pub fn new_with_counts(lowest_discernible_value: u64, highest_trackable_value: u64,
+                           num_significant_digits: u32) -> SimpleHdrHistogram<T, C> {
+        match SimpleHdrHistogram::try_new_with_counts(lowest_discernible_value,
+                                                      highest_trackable_value,
+                                                      num_significant_digits) {
+            Ok(h) => h,
+            Err(err) => panic!("Invalid histogram configuration: {}", err)
+        }
+    }
+
+    /// Like `try_new`, but storing counts in `C` rather than a `Vec<T>`.
+    pub fn try_new_with_counts(lowest_discernible_value: u64, highest_trackable_value: u64,
+                               num_significant_digits: u32)
+            -> Result<SimpleHdrHistogram<T, C>, CreationError> {
+
+        if lowest_discernible_value < 1 {
+            return Err(CreationError::LowIsZero);
+        }
+        // 2 * lowest would overflow, so highest can't possibly be big enough
+        if lowest_discernible_value > u64::max_value() / 2
+                || highest_trackable_value < 2 * lowest_discernible_value {
+            return Err(CreationError::HighLessThanTwiceLow);
+        }
+        if num_significant_digits > 5 {
+            return Err(CreationError::SigFigExceedsMax);
+        }
 
         let largest_value_with_single_unit_resolution = 2_u64 * 10_u64.pow(num_significant_digits);
 
         let unit_magnitude = ((lowest_discernible_value as f64).ln() / 2_f64.ln()) as u32;
-        let unit_magnitude_mask: u64  = (1_u64 << unit_magnitude) - 1;
 
         // find nearest power of 2 to largest_value_with_single_unit_resolution
         let sub_bucket_count_magnitude: u32 =
@@ -240,6 +486,14 @@ impl<T: HistogramCount> SimpleHdrHistogram<T> {
 
         // ugly looking... how should ternaries be done?
         let sub_bucket_half_count_magnitude: u32 = (if sub_bucket_count_magnitude > 1 { sub_bucket_count_magnitude } else { 1 }) - 1;
+
+        // a full sub bucket of values at the lowest discernible value's scale must still fit in
+        // 63 bits, otherwise the bucket index math below overflows
+        if unit_magnitude + sub_bucket_half_count_magnitude > 61 {
+            return Err(CreationError::CannotRepresentSigFigBeyondLow);
+        }
+
+        let unit_magnitude_mask: u64  = (1_u64 << unit_magnitude) - 1;
         let sub_bucket_count: usize = 2_usize.pow(sub_bucket_half_count_magnitude + 1);
         let sub_bucket_half_count: usize = sub_bucket_count / 2;
         // this cast should be safe; see discussion in buckets_needed_for_value on similar cast
@@ -251,20 +505,417 @@ impl<T: HistogramCount> SimpleHdrHistogram<T> {
         // this is a small number (0 - 63) so any usize can hold it
         let leading_zero_count_base: usize = (64_u32 - unit_magnitude - sub_bucket_half_count_magnitude - 1) as usize;
 
-        SimpleHdrHistogram {
+        Ok(SimpleHdrHistogram {
+            lowest_discernible_value: lowest_discernible_value,
+            highest_trackable_value: highest_trackable_value,
+            num_significant_digits: num_significant_digits,
             leading_zeros_count_base: leading_zero_count_base,
             unit_magnitude: unit_magnitude,
             sub_bucket_mask: sub_bucket_mask,
             sub_bucket_count: sub_bucket_count,
             sub_bucket_half_count: sub_bucket_half_count,
             sub_bucket_half_count_magnitude: sub_bucket_half_count_magnitude,
-            counts: vec![T::zero(); counts_arr_len],
+            counts: C::with_len(counts_arr_len),
+            count_type: PhantomData,
             normalizing_index_offset: 0, // 0 for normal Histogram ctor in Java impl
             min_non_zero_value: u64::max_value(),
             total_count: 0,
             max_value: 0,
-            unit_magnitude_mask: unit_magnitude_mask
+            unit_magnitude_mask: unit_magnitude_mask,
+            auto_resize: false,
+            out_of_range_policy: OutOfRangePolicy::Error,
+            out_of_range_count: 0,
+            count_overflow_policy: CountOverflowPolicy::Error,
+            saturated_count: 0,
+            integer_to_double_value_conversion_ratio: 1.0
+        })
+    }
+
+    /// Create a histogram with the same configuration as `layout`, containing `values`.
+    ///
+    /// Fails if any value can't be recorded. To record (value, count) pairs instead, start with
+    /// `new_from` and `extend` it.
+    pub fn new_from_iter<I: IntoIterator<Item = u64>>(layout: &SimpleHdrHistogram<T, C>, values: I)
+            -> Result<SimpleHdrHistogram<T, C>, RecordError> {
+        let mut h = SimpleHdrHistogram::new_from(layout);
+        for value in values {
+            h.record_single_value(value)?;
+        }
+        Ok(h)
+    }
+
+    /// Create a histogram with the same configuration as `layout`, containing the sum of
+    /// `histograms` as per `add`. This is the fallible form of `.sum()`: there's no need for at
+    /// least one histogram, and an addition failing returns its error rather than panicking.
+    ///
+    /// To sum histograms whose ranges differ, use an auto-resizing `layout`.
+    pub fn try_sum<'a, D: Counts<T> + 'a, I>(layout: &SimpleHdrHistogram<T, C>, histograms: I)
+            -> Result<SimpleHdrHistogram<T, C>, AdditionError>
+            where T: 'a, I: IntoIterator<Item = &'a SimpleHdrHistogram<T, D>> {
+        let mut sum = SimpleHdrHistogram::new_from(layout);
+        for h in histograms {
+            sum.add(h)?;
+        }
+        Ok(sum)
+    }
+
+    /// Create an empty histogram with the same configuration as `source`.
+    pub fn new_from(source: &SimpleHdrHistogram<T, C>) -> SimpleHdrHistogram<T, C> {
+        let mut h = SimpleHdrHistogram::new_with_counts(source.lowest_discernible_value,
+                                                        source.highest_trackable_value,
+                                                        source.num_significant_digits);
+        h.auto_resize = source.auto_resize;
+        h.out_of_range_policy = source.out_of_range_policy;
+        h.count_overflow_policy = source.count_overflow_policy;
+        h.integer_to_double_value_conversion_ratio = source.integer_to_double_value_conversion_ratio;
+        h
+    }
+
+    /// Enable or disable auto-resizing. When enabled, recording a value that is beyond the
+    /// highest trackable value grows the histogram to cover it (preserving existing counts)
+    /// rather than failing with `RecordError::ValueOutOfRange`.
+    pub fn set_auto_resize(&mut self, enabled: bool) {
+        self.auto_resize = enabled;
+    }
+
+    pub fn is_auto_resize(&self) -> bool {
+        self.auto_resize
+    }
+
+    /// Choose what happens to values beyond the highest trackable value. Has no effect while
+    /// auto-resizing is enabled, since then every value is in range.
+    pub fn set_out_of_range_policy(&mut self, policy: OutOfRangePolicy) {
+        self.out_of_range_policy = policy;
+    }
+
+    pub fn get_out_of_range_policy(&self) -> OutOfRangePolicy {
+        self.out_of_range_policy
+    }
+
+    /// Choose what happens when the count at a value would overflow the count type.
+    pub fn set_count_overflow_policy(&mut self, policy: CountOverflowPolicy) {
+        self.count_overflow_policy = policy;
+    }
+
+    pub fn get_count_overflow_policy(&self) -> CountOverflowPolicy {
+        self.count_overflow_policy
+    }
+
+    /// Returns the number of values that were discarded because the count at their value was
+    /// already at the count type's max (see `CountOverflowPolicy::Saturate`). These are not
+    /// included in `get_count`.
+    pub fn get_saturated_count(&self) -> u128 {
+        self.saturated_count
+    }
+
+    /// How much one recorded unit is worth in the units values are reported in, as set with
+    /// `HistogramBuilder::value_unit_ratio`. This is Java's integer to double value conversion
+    /// ratio: it's used by `HistogramIterationValue::double_value_iterated_to` and stored in the
+    /// V2 encoding, but doesn't change any other query.
+    pub fn get_value_unit_ratio(&self) -> f64 {
+        self.integer_to_double_value_conversion_ratio
+    }
+
+    /// The highest trackable value the histogram was created with, or grown to by auto-resizing.
+    ///
+    /// Values up to the highest equivalent value of the top bucket can be recorded without
+    /// resizing, so this can be lower than the highest recordable value: `new(1, 1000, 3)` reports
+    /// 1000 but can record values up to 2047.
+    pub fn get_highest_trackable_value(&self) -> u64 {
+        self.highest_trackable_value
+    }
+
+    /// Returns a new histogram with the same configuration, containing this histogram's values
+    /// as if they had been recorded with `record_value_with_expected_interval`.
+    ///
+    /// This is for after-the-fact correction of data that was recorded without it. Do not use it
+    /// on data that was already corrected while recording, or the correction is applied twice.
+    pub fn copy_corrected_for_coordinated_omission(&self, expected_interval: u64)
+            -> Result<SimpleHdrHistogram<T, C>, RecordError> {
+        let mut corrected = SimpleHdrHistogram::new_from(self);
+        for v in self.recorded_values() {
+            corrected.record_value_with_count_and_expected_interval(
+                v.value_iterated_to, v.count_at_value_iterated_to, expected_interval)?;
+        }
+        Ok(corrected)
+    }
+
+    /// Clear all recorded values, keeping the configuration and the allocated counts array (except
+    /// for `PackedCounts`, which frees its pages).
+    pub fn reset(&mut self) {
+        self.counts.reset();
+        self.total_count = 0;
+        self.max_value = 0;
+        self.min_non_zero_value = u64::max_value();
+        self.normalizing_index_offset = 0;
+        self.out_of_range_count = 0;
+        self.saturated_count = 0;
+    }
+
+    /// Replace the contents of `target` with a copy of this histogram's recorded values, reusing
+    /// target's counts array. This works best when `target` has the same layout; otherwise values
+    /// are copied as per `add`.
+    ///
+    /// If this histogram has values beyond what a non-auto-resizing `target` can hold,
+    /// `AdditionError::OtherAddendValueExceedsRange` is returned without modifying `target`. With
+    /// a different layout, several of this histogram's counts can land in one of target's, so
+    /// `AdditionError::CountOverflow` is still possible, and then `target` is left reset.
+    pub fn copy_into<D: Counts<T>>(&self, target: &mut SimpleHdrHistogram<T, D>)
+            -> Result<(), AdditionError> {
+        if !target.auto_resize && target.highest_recordable_value() < self.max_value {
+            return Err(AdditionError::OtherAddendValueExceedsRange);
+        }
+        target.reset();
+        target.add(self)
+    }
+
+    /// The highest value that the counts array can currently hold.
+    fn highest_recordable_value(&self) -> u64 {
+        let last_index = self.counts.len() - 1;
+        self.highest_equivalent_value(self.value_from_index(last_index))
+    }
+
+    /// Add all of `other`'s recorded values to this histogram.
+    ///
+    /// When both histograms have the same unit magnitude and precision, counts are added index by
+    /// index. Otherwise, each of `other`'s recorded values is recorded again in this histogram,
+    /// which may lose precision if this histogram is less precise.
+    ///
+    /// If `other` contains values beyond what this histogram can hold, it is resized if it's
+    /// auto-resizing; otherwise `AdditionError::OtherAddendValueExceedsRange` is returned without
+    /// modifying this histogram. Counts that would overflow are handled according to this
+    /// histogram's `CountOverflowPolicy`; with `CountOverflowPolicy::Error`,
+    /// `AdditionError::CountOverflow` is returned before any counts are added.
+    pub fn add<D: Counts<T>>(&mut self, other: &SimpleHdrHistogram<T, D>)
+            -> Result<(), AdditionError> {
+        if self.highest_recordable_value() < other.max_value {
+            if !self.auto_resize {
+                return Err(AdditionError::OtherAddendValueExceedsRange);
+            }
+            self.resize(other.max_value);
+        }
+
+        if self.unit_magnitude == other.unit_magnitude
+                && self.sub_bucket_count == other.sub_bucket_count {
+            // Same layout, so an index refers to the same values in both histograms. Check for
+            // overflow up front so that a failed addition leaves this histogram untouched.
+            if self.count_overflow_policy == CountOverflowPolicy::Error {
+                for i in 0..other.counts.len() {
+                    let other_count = other.get_count_at_index(i)?;
+                    if other_count == T::zero() {
+                        continue;
+                    }
+                    if self.would_overflow_at_index(i, other_count)? {
+                        return Err(AdditionError::CountOverflow);
+                    }
+                }
+            }
+
+            for i in 0..other.counts.len() {
+                let other_count = other.get_count_at_index(i)?;
+                if other_count != T::zero() {
+                    let added = self.add_to_count_at_index(i, other_count)?;
+                    self.add_to_total_count(added);
+                }
+            }
+
+            self.update_min_and_max(other.max_value);
+            if other.min_non_zero_value != u64::max_value() {
+                self.update_min_and_max(other.min_non_zero_value);
+            }
+        } else {
+            // Several of other's values can land at the same index here, so total them per index
+            // to check for overflow up front. Recorded values are in increasing order, so the
+            // values for an index are consecutive.
+            if self.count_overflow_policy == CountOverflowPolicy::Error {
+                let mut pending: Option<(usize, T)> = None;
+                for v in other.recorded_values() {
+                    let index = self.counts_array_index(v.value_iterated_to);
+                    let count = v.count_at_value_iterated_to;
+                    pending = match pending {
+                        Some((i, total)) if i == index => Some(
+                            (i, total.checked_add(&count).ok_or(AdditionError::CountOverflow)?)),
+                        Some((i, total)) => {
+                            if self.would_overflow_at_index(i, total)? {
+                                return Err(AdditionError::CountOverflow);
+                            }
+                            Some((index, count))
+                        }
+                        None => Some((index, count)),
+                    };
+                }
+                if let Some((i, total)) = pending {
+                    if self.would_overflow_at_index(i, total)? {
+                        return Err(AdditionError::CountOverflow);
+                    }
+                }
+            }
+
+            for v in other.recorded_values() {
+                self.record_value_with_count(v.value_iterated_to, v.count_at_value_iterated_to)?;
+            }
+        }
+
+        self.out_of_range_count += other.out_of_range_count;
+        self.saturated_count += other.saturated_count;
+        Ok(())
+    }
+
+    /// Remove all of `other`'s recorded values from this histogram, e.g. to get the delta between
+    /// two snapshots of a cumulative histogram. Total count, min and max are recalculated from
+    /// the remaining counts.
+    ///
+    /// Fails without modifying this histogram if `other` has values beyond what this histogram
+    /// can hold, or if any count in this histogram would go negative.
+    pub fn subtract<D: Counts<T>>(&mut self, other: &SimpleHdrHistogram<T, D>)
+            -> Result<(), SubtractionError> {
+        if self.highest_recordable_value() < other.max_value {
+            return Err(SubtractionError::SubtrahendValueExceedsMinuendRange);
+        }
+        if self.out_of_range_count < other.out_of_range_count
+                || self.saturated_count < other.saturated_count {
+            return Err(SubtractionError::SubtrahendCountExceedsMinuendCount);
+        }
+
+        if self.unit_magnitude == other.unit_magnitude
+                && self.sub_bucket_count == other.sub_bucket_count {
+            // Same layout, so an index refers to the same values in both histograms. Check every
+            // count before changing any so that a failed subtraction leaves this histogram
+            // untouched.
+            for i in 0..other.counts.len() {
+                let other_count = other.get_count_at_index(i)?;
+                if other_count != T::zero() && self.get_count_at_index(i)? < other_count {
+                    return Err(SubtractionError::SubtrahendCountExceedsMinuendCount);
+                }
+            }
+
+            for i in 0..other.counts.len() {
+                let other_count = other.get_count_at_index(i)?;
+                if other_count != T::zero() {
+                    let the_index =
+                        self.normalize_index(i, self.normalizing_index_offset, self.counts.len())?;
+                    let count = self.counts.get(the_index) - other_count;
+                    self.counts.set(the_index, count);
+                }
+            }
+        } else {
+            // Several of other's indexes may map to the same index here, so subtract from a copy
+            // and only keep it if every count stayed non-negative.
+            let mut counts = self.counts.clone();
+            for v in other.recorded_values() {
+                let the_index = self.normalize_index(self.counts_array_index(v.value_iterated_to),
+                                                     self.normalizing_index_offset, counts.len())?;
+                match counts.get(the_index).checked_sub(&v.count_at_value_iterated_to) {
+                    Some(count) => counts.set(the_index, count),
+                    None => return Err(SubtractionError::SubtrahendCountExceedsMinuendCount)
+                };
+            }
+            self.counts = counts;
+        }
+
+        self.out_of_range_count -= other.out_of_range_count;
+        self.saturated_count -= other.saturated_count;
+        self.establish_internal_tracking_values();
+        Ok(())
+    }
+
+    /// Recalculate total count, min and max from the counts array.
+    fn establish_internal_tracking_values(&mut self) {
+        let mut total_count = 0;
+        let mut max_index = None;
+        let mut min_non_zero_index = None;
+        for i in 0..self.counts.len() {
+            // nothing can be recorded outside the counts array
+            let count = self.get_count_at_index(i).unwrap_or(T::zero());
+            if count != T::zero() {
+                // we only use u8 - u128 types, so this must always work
+                total_count += count.to_u128().unwrap();
+                max_index = Some(i);
+                if min_non_zero_index.is_none() && i != 0 {
+                    min_non_zero_index = Some(i);
+                }
+            }
+        }
+
+        self.total_count = total_count;
+        self.max_value = 0;
+        self.min_non_zero_value = u64::max_value();
+        if let Some(i) = max_index {
+            let max_value = self.highest_equivalent_value(self.value_from_index(i));
+            self.update_max_value(max_value);
+        }
+        if let Some(i) = min_non_zero_index {
+            let min_non_zero_value = self.value_from_index(i);
+            self.update_min_non_zero_value(min_non_zero_value);
+        }
+    }
+
+    /// Where to record `count` occurrences of `value`: the counts index and the value to update
+    /// min and max with, which is only different from `value` if it's clamped. If `value` is
+    /// beyond the counts array, the histogram is resized or the out of range policy applied, and
+    /// None means the count was dropped.
+    fn index_to_record_at(&mut self, value: u64, count: u128)
+            -> Result<Option<(usize, u64)>, RecordError> {
+        let counts_index = self.counts_array_index(value);
+        if counts_index < self.counts.len() {
+            return Ok(Some((counts_index, value)));
+        }
+        if self.auto_resize {
+            // resizing doesn't move existing indexes
+            self.resize(value);
+            return Ok(Some((counts_index, value)));
+        }
+        match self.out_of_range_policy {
+            OutOfRangePolicy::Error => Err(RecordError::ValueOutOfRange),
+            OutOfRangePolicy::Clamp => {
+                let counts_index = self.counts.len() - 1;
+                Ok(Some((counts_index,
+                         self.highest_equivalent_value(self.value_from_index(counts_index)))))
+            }
+            OutOfRangePolicy::Drop => {
+                self.out_of_range_count += count;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Like `record_value_with_count`, but `count` may be more than the count type can hold, in
+    /// which case it overflows as per the `CountOverflowPolicy`.
+    fn record_value_with_u64_count(&mut self, value: u64, count: u64) -> Result<(), RecordError> {
+        if let Some(count) = T::from_u64(count) {
+            return self.record_value_with_count(value, count);
+        }
+        let (counts_index, value) = match self.index_to_record_at(value, u128::from(count))? {
+            Some(index_and_value) => index_and_value,
+            None => return Ok(())
+        };
+        if self.count_overflow_policy == CountOverflowPolicy::Error {
+            return Err(RecordError::CountOverflow);
+        }
+        // count didn't fit in T, so T is narrower than u64 and its max converts
+        let max = T::max_value();
+        let added = self.add_to_count_at_index(counts_index, max)?;
+        self.saturated_count += u128::from(count - max.to_u64().unwrap());
+        self.update_min_and_max(value);
+        self.add_to_total_count(added);
+        Ok(())
+    }
+
+    fn record_value_with_count_and_expected_interval(&mut self, value: u64, count: T,
+                                                     expected_interval: u64) -> Result<(), RecordError> {
+        self.record_value_with_count(value, count)?;
+        if expected_interval == 0 || value <= expected_interval {
+            return Ok(());
+        }
+
+        // back-fill the samples that a stalled recorder failed to take while this value was
+        // being measured
+        let mut missing_value = value - expected_interval;
+        while missing_value >= expected_interval {
+            self.record_value_with_count(missing_value, count)?;
+            missing_value -= expected_interval;
         }
+        Ok(())
     }
 
     fn buckets_needed_for_value(value: u64, sub_bucket_count: usize, unit_magnitude: u32) -> usize {
@@ -292,6 +943,30 @@ impl<T: HistogramCount> SimpleHdrHistogram<T> {
         (bucket_count + 1) * (sub_bucket_count / 2)
     }
 
+    /// Grow the counts array so that it can hold `new_highest_trackable_value`. Existing counts
+    /// stay at their indexes, so the new buckets are simply appended.
+    fn resize(&mut self, new_highest_trackable_value: u64) {
+        let bucket_count = SimpleHdrHistogram::<T>::buckets_needed_for_value(
+            new_highest_trackable_value, self.sub_bucket_count, self.unit_magnitude);
+        let counts_arr_len = SimpleHdrHistogram::<T>::counts_arr_len(bucket_count,
+                                                                     self.sub_bucket_count);
+        if self.normalizing_index_offset != 0 {
+            // Shifted counts wrap around the end of the array, so appending would put the new
+            // buckets in the middle. Unwrap them into a fresh array instead.
+            let mut counts = C::with_len(counts_arr_len);
+            for i in 0..self.counts.len() {
+                // i is within the old counts array
+                counts.set(i, self.get_count_at_index(i).unwrap_or(T::zero()));
+            }
+            self.counts = counts;
+            self.normalizing_index_offset = 0;
+        }
+        self.counts.grow(counts_arr_len);
+
+        let last_index = self.counts.len() - 1;
+        self.highest_trackable_value = self.highest_equivalent_value(self.value_from_index(last_index));
+    }
+
     fn value_from_index(&self, index: usize) -> u64 {
         // Dividing by sub bucket half count will yield 1 in top half of first bucket, 2 in
         // 2nd bucket, etc, so subtract 1.
@@ -310,12 +985,23 @@ impl<T: HistogramCount> SimpleHdrHistogram<T> {
         self.value_from_index_sub(bucket_index as usize, sub_bucket_index)
     }
 
-    fn get_count_at_index(&self, index: usize) -> Result<T, String> {
+    /// Sum of counts from low_index to high_index, inclusive.
+    fn count_between_indexes(&self, low_index: usize, high_index: usize) -> u128 {
+        let mut count = 0;
+        for i in low_index..(high_index + 1) {
+            // nothing can be recorded outside the counts array, and we only use u8 - u128 types, so
+            // the conversion must always work
+            count += self.get_count_at_index(i).unwrap_or(T::zero()).to_u128().unwrap();
+        }
+        count
+    }
+
+    fn get_count_at_index(&self, index: usize) -> Result<T, QueryError> {
         let normalized_index =
         self.normalize_index(index, self.normalizing_index_offset, self.counts.len());
         match normalized_index {
             Ok(the_index) =>
-            Ok(self.counts[the_index]),
+            Ok(self.counts.get(the_index)),
             Err(err) =>
             Err(err)
         }
@@ -326,8 +1012,9 @@ impl<T: HistogramCount> SimpleHdrHistogram<T> {
         (sub_bucket_index as u64) << (bucket_index as u32 + self.unit_magnitude)
     }
 
-    fn increment_total_count(&mut self) {
-        self.total_count += 1;
+    fn add_to_total_count(&mut self, count: T) {
+        // we only use u8 - u128 types, so this must always work
+        self.total_count += count.to_u128().unwrap();
     }
 
     fn update_max_value(&mut self, value: u64) {
@@ -352,16 +1039,33 @@ impl<T: HistogramCount> SimpleHdrHistogram<T> {
         }
     }
 
-    fn increment_count_at_index(&mut self, index: usize) -> Result<(), String> {
-        let normalized_index =
-            self.normalize_index(index, self.normalizing_index_offset, self.counts.len());
-        match normalized_index {
-            Ok(the_index) => {
-                // TODO express exceeding the counts size as an error here?
-                self.counts[the_index] = self.counts[the_index] + T::one();
-                Ok(())
+    /// Add `count` at `index`, applying the count overflow policy. Returns how much was actually
+    /// added, which is less than `count` if it saturated.
+    /// Whether adding `count` to the count at `index` would overflow the count type.
+    fn would_overflow_at_index(&self, index: usize, count: T) -> Result<bool, QueryError> {
+        Ok(self.get_count_at_index(index)?.checked_add(&count).is_none())
+    }
+
+    fn add_to_count_at_index(&mut self, index: usize, count: T) -> Result<T, RecordError> {
+        let the_index =
+            self.normalize_index(index, self.normalizing_index_offset, self.counts.len())?;
+        let current = self.counts.get(the_index);
+        match current.checked_add(&count) {
+            Some(new_count) => {
+                self.counts.set(the_index, new_count);
+                Ok(count)
+            }
+            None => match self.count_overflow_policy {
+                CountOverflowPolicy::Error => Err(RecordError::CountOverflow),
+                CountOverflowPolicy::Saturate => {
+                    // current + count overflowed, so count > max - current
+                    let added = T::max_value() - current;
+                    self.counts.set(the_index, T::max_value());
+                    // we only use u8 - u128 types, so this must always work
+                    self.saturated_count += (count - added).to_u128().unwrap();
+                    Ok(added)
+                }
             }
-            Err(err) => Err(err)
         }
     }
 
@@ -370,7 +1074,9 @@ impl<T: HistogramCount> SimpleHdrHistogram<T> {
     ///
     /// Left shifts have positive offsets. This means to read an existing count, you need to use
     /// a higher value to reach a higher index that then has the positive offset subtracted off.
-    /// Analogously, right shifts have negative offsets.
+    /// Analogously, right shifts have negative offsets. (`shift_values_left` and
+    /// `shift_values_right` store the offset modulo the array length, so after a right shift it's
+    /// the equivalent positive offset.)
     ///
     /// When a shift takes place, it is checked for over/underflow, but after a shift has happened,
     /// the user could still want to record a value that might have over/underflowed if it had been
@@ -379,12 +1085,12 @@ impl<T: HistogramCount> SimpleHdrHistogram<T> {
     /// resulting subtraction to the top of the array. If a right shift has happened, the offset is
     /// negative, so we wrap overflow to the bottom.
     fn normalize_index(&self, index: usize, normalizing_index_offset: i32, array_length: usize) ->
-    Result<usize, String> {
+    Result<usize, QueryError> {
         match normalizing_index_offset {
             0 => Ok(index),
             _ =>
             if index > array_length {
-                Err(String::from("index out of covered range"))
+                Err(QueryError::IndexOutOfRange)
             } else {
                 // indices are always pretty small since they scale to values exponentially
                 let array_length_i32 : i32 = array_length as i32;
@@ -456,53 +1162,149 @@ impl<T: HistogramCount> SimpleHdrHistogram<T> {
     }
 }
 
-pub struct RecordedValues<'a, T: HistogramCount + 'a> {
-    histo: &'a SimpleHdrHistogram<T>
+/// Adds `other` to this histogram as per `add`.
+///
+/// Panics if the addition fails; use `add` to handle failure.
+impl<'a, T: HistogramCount, C: Counts<T>, D: Counts<T>> AddAssign<&'a SimpleHdrHistogram<T, D>>
+        for SimpleHdrHistogram<T, C> {
+    fn add_assign(&mut self, other: &'a SimpleHdrHistogram<T, D>) {
+        if let Err(err) = self.add(other) {
+            panic!("Could not add histogram: {}", err);
+        }
+    }
+}
+
+/// Subtracts `other` from this histogram as per `subtract`.
+///
+/// Panics if the subtraction fails; use `subtract` to handle failure.
+impl<'a, T: HistogramCount, C: Counts<T>, D: Counts<T>> SubAssign<&'a SimpleHdrHistogram<T, D>>
+        for SimpleHdrHistogram<T, C> {
+    fn sub_assign(&mut self, other: &'a SimpleHdrHistogram<T, D>) {
+        if let Err(err) = self.subtract(other) {
+            panic!("Could not subtract histogram: {}", err);
+        }
+    }
+}
+
+/// Records each value as per `record_single_value`.
+///
+/// Panics if a value can't be recorded; use `record_single_value` to handle failure.
+impl<T: HistogramCount, C: Counts<T>> Extend<u64> for SimpleHdrHistogram<T, C> {
+    fn extend<I: IntoIterator<Item = u64>>(&mut self, values: I) {
+        for value in values {
+            if let Err(err) = self.record_single_value(value) {
+                panic!("Could not record value {}: {}", value, err);
+            }
+        }
+    }
+}
+
+/// Records each (value, count) pair as per `record_value_with_count`. A count too big for the
+/// count type can never fit, so it overflows as per the `CountOverflowPolicy`.
+///
+/// Panics if a value can't be recorded; use `record_value_with_count` to handle failure.
+impl<T: HistogramCount, C: Counts<T>> Extend<(u64, u64)> for SimpleHdrHistogram<T, C> {
+    fn extend<I: IntoIterator<Item = (u64, u64)>>(&mut self, values: I) {
+        for (value, count) in values {
+            if let Err(err) = self.record_value_with_u64_count(value, count) {
+                panic!("Could not record value {}: {}", value, err);
+            }
+        }
+    }
+}
+
+/// Adds up histograms as per `add`, starting from the first one, so the sum has its
+/// configuration.
+///
+/// # Panics
+///
+/// If there are no histograms, since then there's no configuration to use, or if an addition
+/// fails, e.g. because a later histogram has values beyond the first one's range and the first
+/// one isn't auto-resizing. Use `SimpleHdrHistogram::try_sum` to handle those.
+impl<T: HistogramCount, C: Counts<T>> Sum for SimpleHdrHistogram<T, C> {
+    fn sum<I: Iterator<Item = SimpleHdrHistogram<T, C>>>(mut histograms: I)
+            -> SimpleHdrHistogram<T, C> {
+        let mut sum = match histograms.next() {
+            Some(h) => h,
+            None => panic!("Cannot sum zero histograms")
+        };
+        for h in histograms {
+            sum += &h;
+        }
+        sum
+    }
+}
+
+/// Like summing owned histograms, but copying the first one.
+///
+/// # Panics
+///
+/// In the same cases as summing owned histograms. Use `SimpleHdrHistogram::try_sum` to handle
+/// those.
+impl<'a, T: HistogramCount, C: Counts<T>> Sum<&'a SimpleHdrHistogram<T, C>>
+        for SimpleHdrHistogram<T, C> {
+    fn sum<I: Iterator<Item = &'a SimpleHdrHistogram<T, C>>>(histograms: I)
+            -> SimpleHdrHistogram<T, C> {
+        let mut histograms = histograms.peekable();
+        let first = match histograms.peek() {
+            Some(first) => *first,
+            None => panic!("Cannot sum zero histograms")
+        };
+        match SimpleHdrHistogram::try_sum(first, histograms) {
+            Ok(sum) => sum,
+            Err(err) => panic!("Could not add histogram: {}", err)
+        }
+    }
+}
+
+pub struct RecordedValues<'a, T: HistogramCount + 'a, C: Counts<T> + 'a = Vec<T>> {
+    histo: &'a SimpleHdrHistogram<T, C>
 }
 
-pub struct AllValues<'a, T: HistogramCount + 'a> {
-    histo: &'a SimpleHdrHistogram<T>
+pub struct AllValues<'a, T: HistogramCount + 'a, C: Counts<T> + 'a = Vec<T>> {
+    histo: &'a SimpleHdrHistogram<T, C>
 }
 
-pub struct LogarithmicValues<'a, T: HistogramCount + 'a> {
-    histo: &'a SimpleHdrHistogram<T>,
+pub struct LogarithmicValues<'a, T: HistogramCount + 'a, C: Counts<T> + 'a = Vec<T>> {
+    histo: &'a SimpleHdrHistogram<T, C>,
     value_units_in_first_bucket: u64,
     log_base: u64
 }
 
-pub struct LinearValues<'a, T: HistogramCount + 'a> {
-    histo: &'a SimpleHdrHistogram<T>,
+pub struct LinearValues<'a, T: HistogramCount + 'a, C: Counts<T> + 'a = Vec<T>> {
+    histo: &'a SimpleHdrHistogram<T, C>,
     value_units_per_bucket: u64
 }
 
-pub struct Percentiles<'a, T: HistogramCount + 'a> {
-    histo: &'a SimpleHdrHistogram<T>,
+pub struct Percentiles<'a, T: HistogramCount + 'a, C: Counts<T> + 'a = Vec<T>> {
+    histo: &'a SimpleHdrHistogram<T, C>,
     percentile_ticks_per_half_distance: u32
 }
 
-pub trait IterationStrategy<'a, T: HistogramCount + 'a> : Sized {
-    fn increment_iteration_level(&mut self, iter: &BaseHistogramIterator<'a, T, Self>);
+pub trait IterationStrategy<'a, T: HistogramCount + 'a, C: Counts<T> + 'a = Vec<T>> : Sized {
+    fn increment_iteration_level(&mut self, iter: &BaseHistogramIterator<'a, T, Self, C>);
     /// return true if we've reached a position that should be emitted to the consumer of the
     /// Iterable
-    fn reached_iteration_level(&self, iter: &BaseHistogramIterator<'a, T, Self>) -> bool;
+    fn reached_iteration_level(&self, iter: &BaseHistogramIterator<'a, T, Self, C>) -> bool;
 
     /// return false if iteration is done and we should return None to the consumer of the
     /// Iterator. Analog of Java impl's hasNext().
-    fn allow_further_iteration(&mut self, iter: &BaseHistogramIterator<'a, T, Self>) -> bool {
+    fn allow_further_iteration(&mut self, iter: &BaseHistogramIterator<'a, T, Self, C>) -> bool {
         self._default_allow_further_iteration(iter)
     }
 
     /// default used by several implementations. Helper to allow overrides to access original logic
-    fn _default_allow_further_iteration(&self, iter: &BaseHistogramIterator<'a, T, Self>) -> bool {
+    fn _default_allow_further_iteration(&self, iter: &BaseHistogramIterator<'a, T, Self, C>)
+            -> bool {
         iter.total_count_to_current_index < iter.array_total_count
     }
 
     /// the value exposed to the consumer of the iterator at a given iteration point
-    fn value_iterated_to(&self, iter: &BaseHistogramIterator<'a, T, Self>) -> u64 {
+    fn value_iterated_to(&self, iter: &BaseHistogramIterator<'a, T, Self, C>) -> u64 {
         iter.histogram.highest_equivalent_value(iter.current_value_at_index)
     }
 
-    fn percentile_iterated_to(&self, iter: &BaseHistogramIterator<'a, T, Self>) -> f64 {
+    fn percentile_iterated_to(&self, iter: &BaseHistogramIterator<'a, T, Self, C>) -> f64 {
         // default to the current percentile
         (100.0 * iter.total_count_to_current_index as f64) / iter.array_total_count as f64
     }
@@ -514,18 +1316,19 @@ pub trait IterationStrategy<'a, T: HistogramCount + 'a> : Sized {
 }
 
 #[derive(Debug)]
-pub struct BaseHistogramIterator<'a, T: HistogramCount + 'a, S: IterationStrategy<'a, T>> {
-    histogram: &'a SimpleHdrHistogram<T>,
+pub struct BaseHistogramIterator<'a, T: HistogramCount + 'a, S: IterationStrategy<'a, T, C>,
+        C: Counts<T> + 'a = Vec<T>> {
+    histogram: &'a SimpleHdrHistogram<T, C>,
     strategy: S,
-    saved_histogram_total_raw_count: u64,
+    saved_histogram_total_raw_count: u128,
     current_index: usize,
     current_value_at_index: u64,
     next_value_at_index: u64,
     prev_value_iterated_to: u64,
-    total_count_to_prev_index: u64,
-    total_count_to_current_index: u64,
-    total_value_to_current_index: u64,
-    array_total_count: u64,
+    total_count_to_prev_index: u128,
+    total_count_to_current_index: u128,
+    total_value_to_current_index: u128,
+    array_total_count: u128,
     count_at_this_value: T,
     fresh_sub_bucket: bool,
     current_iteration_value: HistogramIterationValue<T>,
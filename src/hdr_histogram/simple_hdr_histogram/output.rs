@@ -0,0 +1,81 @@
+use std::io;
+use std::io::Write;
+
+use hdr_histogram::simple_hdr_histogram::*;
+
+/// Layout for `output_percentile_distribution`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PercentileOutputFormat {
+    /// The `.hgrm` layout: aligned columns followed by a footer with the mean, standard
+    /// deviation, max and total count. This is what the HdrHistogram plotter reads.
+    Classic,
+    /// Comma separated columns with a header row and no footer.
+    Csv,
+}
+
+impl<T: HistogramCount, C: Counts<T>> SimpleHdrHistogram<T, C> {
+    /// Write a table of values at percentiles, as produced by the Java and C implementations.
+    ///
+    /// percentile_ticks_per_half_distance: the number of reporting points per exponentially
+    /// decreasing half-distance to 100%, as for `percentiles`
+    /// output_value_unit_scaling_ratio: values are divided by this before being written, e.g.
+    /// 1,000,000.0 to write nanosecond values in milliseconds
+    pub fn output_percentile_distribution<W: Write>(&self, writer: &mut W,
+                                                    percentile_ticks_per_half_distance: u32,
+                                                    output_value_unit_scaling_ratio: f64,
+                                                    format: PercentileOutputFormat)
+                                                    -> io::Result<()> {
+        // one decimal place per significant digit
+        let precision = self.num_significant_digits as usize;
+
+        match format {
+            PercentileOutputFormat::Classic => writeln!(writer, "{:>12} {:>14} {:>10} {:>14}\n",
+                                                        "Value", "Percentile", "TotalCount",
+                                                        "1/(1-Percentile)")?,
+            PercentileOutputFormat::Csv =>
+                writeln!(writer, "\"Value\",\"Percentile\",\"TotalCount\",\"1/(1-Percentile)\"")?,
+        }
+
+        for v in self.percentiles(percentile_ticks_per_half_distance) {
+            let value = v.value_iterated_to as f64 / output_value_unit_scaling_ratio;
+            let percentile = v.percentile_level_iterated_to / 100.0;
+            // the last line is at 100%, where 1/(1-percentile) is infinite
+            let is_last = v.percentile_level_iterated_to == 100.0;
+
+            match (format, is_last) {
+                (PercentileOutputFormat::Classic, false) =>
+                    writeln!(writer, "{:12.*} {:2.12} {:10} {:14.2}", precision, value, percentile,
+                             v.total_count_to_this_value, 1.0 / (1.0 - percentile))?,
+                (PercentileOutputFormat::Classic, true) =>
+                    writeln!(writer, "{:12.*} {:2.12} {:10}", precision, value, percentile,
+                             v.total_count_to_this_value)?,
+                (PercentileOutputFormat::Csv, false) =>
+                    writeln!(writer, "{:.*},{:.12},{},{:.2}", precision, value, percentile,
+                             v.total_count_to_this_value, 1.0 / (1.0 - percentile))?,
+                (PercentileOutputFormat::Csv, true) =>
+                    writeln!(writer, "{:.*},{:.12},{},Infinity", precision, value, percentile,
+                             v.total_count_to_this_value)?,
+            }
+        }
+
+        if format == PercentileOutputFormat::Classic {
+            let max = if self.get_max() == 0 {
+                0
+            } else {
+                self.highest_equivalent_value(self.get_max())
+            };
+            // counts_arr_len is (bucket count + 1) * sub bucket half count
+            let bucket_count = self.counts.len() / self.sub_bucket_half_count - 1;
+
+            writeln!(writer, "#[Mean    = {:12.*}, StdDeviation   = {:12.*}]",
+                     precision, self.mean() / output_value_unit_scaling_ratio,
+                     precision, self.stdev() / output_value_unit_scaling_ratio)?;
+            writeln!(writer, "#[Max     = {:12.*}, Total count    = {:12}]",
+                     precision, max as f64 / output_value_unit_scaling_ratio, self.get_count())?;
+            writeln!(writer, "#[Buckets = {:12}, SubBuckets     = {:12}]",
+                     bucket_count, self.sub_bucket_count)?;
+        }
+
+        Ok(())
+    }
+}
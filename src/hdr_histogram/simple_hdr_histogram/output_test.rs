@@ -0,0 +1,77 @@
+use hdr_histogram::simple_hdr_histogram::*;
+
+fn output(h: &SimpleHdrHistogram<u64>, ticks: u32, ratio: f64, format: PercentileOutputFormat)
+        -> String {
+    let mut buf = Vec::new();
+    h.output_percentile_distribution(&mut buf, ticks, ratio, format).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+/// 1 * 1000, 2 * 2000, ... 10 * 10_000
+fn histo_1_to_10() -> SimpleHdrHistogram<u64> {
+    let mut h = SimpleHdrHistogram::<u64>::new(1, 3600 * 1000 * 1000, 3);
+    for i in 1..11 {
+        h.record_value_with_count(i * 1000, i).unwrap();
+    }
+    h
+}
+
+#[test]
+fn output_classic() {
+    let expected = "       Value     Percentile TotalCount 1/(1-Percentile)\n\
+                    \n\
+                    \x20      1.000 0.000000000000          1           1.00\n\
+                    \x20      5.003 0.250000000000         15           1.33\n\
+                    \x20      7.003 0.500000000000         28           2.00\n\
+                    \x20      8.003 0.625000000000         36           2.67\n\
+                    \x20      9.007 0.750000000000         45           4.00\n\
+                    \x20      9.007 0.812500000000         45           5.33\n\
+                    \x20     10.007 0.875000000000         55           8.00\n\
+                    \x20     10.007 1.000000000000         55\n\
+                    #[Mean    =        7.002, StdDeviation   =        2.451]\n\
+                    #[Max     =       10.007, Total count    =           55]\n\
+                    #[Buckets =           22, SubBuckets     =         2048]\n";
+
+    assert_eq!(expected, output(&histo_1_to_10(), 2, 1000.0, PercentileOutputFormat::Classic));
+}
+
+#[test]
+fn output_csv() {
+    let expected = "\"Value\",\"Percentile\",\"TotalCount\",\"1/(1-Percentile)\"\n\
+                    1.000,0.000000000000,1,1.00\n\
+                    5.003,0.250000000000,15,1.33\n\
+                    7.003,0.500000000000,28,2.00\n\
+                    8.003,0.625000000000,36,2.67\n\
+                    9.007,0.750000000000,45,4.00\n\
+                    9.007,0.812500000000,45,5.33\n\
+                    10.007,0.875000000000,55,8.00\n\
+                    10.007,1.000000000000,55,Infinity\n";
+
+    assert_eq!(expected, output(&histo_1_to_10(), 2, 1000.0, PercentileOutputFormat::Csv));
+}
+
+#[test]
+fn output_unscaled_uses_sig_fig_precision() {
+    let mut h = SimpleHdrHistogram::<u64>::new(1, 100_000, 1);
+    h.record_single_value(7).unwrap();
+
+    let out = output(&h, 5, 1.0, PercentileOutputFormat::Classic);
+    let lines: Vec<&str> = out.lines().collect();
+
+    assert_eq!("         7.0 0.000000000000          1           1.00", lines[2]);
+    assert_eq!("         7.0 1.000000000000          1", lines[3]);
+    assert_eq!("#[Max     =          7.0, Total count    =            1]", lines[5]);
+}
+
+#[test]
+fn output_empty() {
+    let h = SimpleHdrHistogram::<u64>::new(1, 3600 * 1000 * 1000, 3);
+
+    let expected = "       Value     Percentile TotalCount 1/(1-Percentile)\n\
+                    \n\
+                    #[Mean    =        0.000, StdDeviation   =        0.000]\n\
+                    #[Max     =        0.000, Total count    =            0]\n\
+                    #[Buckets =           22, SubBuckets     =         2048]\n";
+
+    assert_eq!(expected, output(&h, 5, 1.0, PercentileOutputFormat::Classic));
+}
@@ -0,0 +1,111 @@
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::atomic::AtomicI64;
+use std::sync::atomic::Ordering;
+use std::thread;
+
+/// Lets wait-free writers share data structures with a reader that occasionally needs to know
+/// that all writes to a structure have finished, as in the Java `WriterReaderPhaser`.
+///
+/// Writers wrap each write in `writer_critical_section_enter`. A reader takes `reader_lock`,
+/// points writers at a different structure, then calls `flip_phase`, which waits until every
+/// writer that might have seen the old structure has left its critical section.
+///
+/// Writers never block or retry; only the reader waits.
+#[derive(Debug)]
+pub struct WriterReaderPhaser {
+    /// Incremented on entering a critical section. Its sign says which phase writers are in:
+    /// even phases count up from 0 and odd phases count up from i64::MIN.
+    start_epoch: AtomicI64,
+    /// Incremented on leaving a critical section entered in an even phase
+    even_end_epoch: AtomicI64,
+    /// Incremented on leaving a critical section entered in an odd phase
+    odd_end_epoch: AtomicI64,
+    reader_lock: Mutex<()>,
+}
+
+/// A writer's critical section, which ends when this is dropped.
+#[derive(Debug)]
+pub struct WriterCriticalSection<'a> {
+    phaser: &'a WriterReaderPhaser,
+    start_epoch: i64,
+}
+
+/// Exclusive access for a reader, released when this is dropped.
+#[derive(Debug)]
+pub struct PhaserReaderGuard<'a> {
+    phaser: &'a WriterReaderPhaser,
+    _lock: MutexGuard<'a, ()>,
+}
+
+impl WriterReaderPhaser {
+    pub fn new() -> WriterReaderPhaser {
+        WriterReaderPhaser {
+            start_epoch: AtomicI64::new(0),
+            even_end_epoch: AtomicI64::new(0),
+            odd_end_epoch: AtomicI64::new(i64::min_value()),
+            reader_lock: Mutex::new(()),
+        }
+    }
+
+    /// Enter a writer critical section. This is wait-free.
+    pub fn writer_critical_section_enter(&self) -> WriterCriticalSection<'_> {
+        WriterCriticalSection {
+            phaser: self,
+            start_epoch: self.start_epoch.fetch_add(1, Ordering::SeqCst),
+        }
+    }
+
+    /// Block until no other reader holds the lock.
+    pub fn reader_lock(&self) -> PhaserReaderGuard<'_> {
+        PhaserReaderGuard {
+            phaser: self,
+            // the lock protects no data, so a panic while it was held can't have broken anything
+            _lock: self.reader_lock.lock().unwrap_or_else(|e| e.into_inner()),
+        }
+    }
+}
+
+impl Default for WriterReaderPhaser {
+    fn default() -> WriterReaderPhaser {
+        WriterReaderPhaser::new()
+    }
+}
+
+impl<'a> Drop for WriterCriticalSection<'a> {
+    fn drop(&mut self) {
+        let end_epoch = if self.start_epoch < 0 {
+            &self.phaser.odd_end_epoch
+        } else {
+            &self.phaser.even_end_epoch
+        };
+        end_epoch.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl<'a> PhaserReaderGuard<'a> {
+    /// Start a new phase, and wait until all writer critical sections entered in the previous
+    /// phase have ended. Writers that enter after this starts are in the new phase, so they
+    /// don't hold it up.
+    pub fn flip_phase(&self) {
+        let phaser = self.phaser;
+        let next_phase_is_even = phaser.start_epoch.load(Ordering::SeqCst) < 0;
+
+        let initial_start_value = if next_phase_is_even { 0 } else { i64::min_value() };
+        let (next_end_epoch, previous_end_epoch) = if next_phase_is_even {
+            (&phaser.even_end_epoch, &phaser.odd_end_epoch)
+        } else {
+            (&phaser.odd_end_epoch, &phaser.even_end_epoch)
+        };
+
+        // No writers are in the next phase yet, so its end epoch can be reset before writers
+        // are switched over to it.
+        next_end_epoch.store(initial_start_value, Ordering::SeqCst);
+        let start_value_at_flip = phaser.start_epoch.swap(initial_start_value, Ordering::SeqCst);
+
+        // every writer that entered the previous phase increments its end epoch once on exit
+        while previous_end_epoch.load(Ordering::SeqCst) != start_value_at_flip {
+            thread::yield_now();
+        }
+    }
+}
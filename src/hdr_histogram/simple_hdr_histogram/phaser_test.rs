@@ -0,0 +1,49 @@
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
+
+use hdr_histogram::simple_hdr_histogram::*;
+
+#[test]
+fn flip_phase_without_writers() {
+    let phaser = WriterReaderPhaser::new();
+
+    let reader = phaser.reader_lock();
+    reader.flip_phase();
+    reader.flip_phase();
+    reader.flip_phase();
+}
+
+#[test]
+fn flip_phase_after_writers_exit() {
+    let phaser = WriterReaderPhaser::new();
+    for _ in 0..3 {
+        drop(phaser.writer_critical_section_enter());
+        phaser.reader_lock().flip_phase();
+    }
+}
+
+#[test]
+fn flip_phase_waits_for_writer() {
+    let phaser = Arc::new(WriterReaderPhaser::new());
+    let flipped = Arc::new(AtomicBool::new(false));
+
+    let writer = phaser.writer_critical_section_enter();
+    let reader_thread = {
+        let phaser = phaser.clone();
+        let flipped = flipped.clone();
+        thread::spawn(move || {
+            phaser.reader_lock().flip_phase();
+            flipped.store(true, Ordering::SeqCst);
+        })
+    };
+
+    thread::sleep(Duration::from_millis(50));
+    assert!(!flipped.load(Ordering::SeqCst));
+
+    drop(writer);
+    reader_thread.join().unwrap();
+    assert!(flipped.load(Ordering::SeqCst));
+}
@@ -0,0 +1,109 @@
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use hdr_histogram::simple_hdr_histogram::*;
+
+/// Records values from many threads into interval histograms, as in the Java `Recorder`.
+///
+/// Recording threads call `record_single_value` or `record_value_with_count`, which are
+/// wait-free. A reporting thread periodically calls `get_interval_histogram` to collect
+/// everything recorded since its previous call while recording carries on into a fresh
+/// histogram. Each recorded value ends up in exactly one interval histogram.
+///
+/// Internally, recording goes into one of two `AtomicHistogram`s. Collecting an interval swaps
+/// which one is active and waits (via a `WriterReaderPhaser`) for recordings already in progress
+/// on the old one to finish before copying it out.
+#[derive(Debug)]
+pub struct Recorder {
+    histograms: [AtomicHistogram; 2],
+    /// index in `histograms` of the histogram being recorded into
+    active_index: AtomicUsize,
+    phaser: WriterReaderPhaser,
+}
+
+impl Recorder {
+    /// lowest_discernible_value: must be >= 1
+    /// highest_trackable_value: must be >= 2 * lowest_discernible_value
+    /// num_significant_digits: must be <= 5
+    ///
+    /// Panics if the configuration is invalid. Use `try_new` to handle that instead.
+    pub fn new(lowest_discernible_value: u64, highest_trackable_value: u64,
+               num_significant_digits: u32) -> Recorder {
+        match Recorder::try_new(lowest_discernible_value, highest_trackable_value,
+                                num_significant_digits) {
+            Ok(r) => r,
+            Err(e) => panic!("Invalid histogram configuration: {}", e)
+        }
+    }
+
+    /// Like `new`, but returns an error rather than panicking on an invalid configuration.
+    pub fn try_new(lowest_discernible_value: u64, highest_trackable_value: u64,
+                   num_significant_digits: u32) -> Result<Recorder, CreationError> {
+        let first = AtomicHistogram::try_new(lowest_discernible_value, highest_trackable_value,
+                                             num_significant_digits)?;
+        let second = AtomicHistogram::try_new(lowest_discernible_value, highest_trackable_value,
+                                              num_significant_digits)?;
+        Ok(Recorder {
+            histograms: [first, second],
+            active_index: AtomicUsize::new(0),
+            phaser: WriterReaderPhaser::new(),
+        })
+    }
+
+    /// Set what to do with values beyond the highest trackable value.
+    pub fn set_out_of_range_policy(&mut self, policy: OutOfRangePolicy) {
+        for h in self.histograms.iter_mut() {
+            h.set_out_of_range_policy(policy);
+        }
+    }
+
+    pub fn record_single_value(&self, value: u64) -> Result<(), RecordError> {
+        self.record_value_with_count(value, 1)
+    }
+
+    pub fn record_value_with_count(&self, value: u64, count: u64) -> Result<(), RecordError> {
+        let _critical_section = self.phaser.writer_critical_section_enter();
+        self.histograms[self.active_index.load(Ordering::SeqCst)]
+            .record_value_with_count(value, count)
+    }
+
+    /// Get everything recorded since the previous interval histogram was collected (or since the
+    /// recorder was created or reset), and start a new interval.
+    pub fn get_interval_histogram(&self) -> SimpleHdrHistogram<u64> {
+        let mut interval = None;
+        self.perform_interval_sample(|finished| interval = Some(finished.snapshot()));
+        // perform_interval_sample always calls the closure
+        interval.unwrap()
+    }
+
+    /// Like `get_interval_histogram`, but replaces the contents of `target` rather than
+    /// allocating a new histogram. Pass in the previous interval histogram once done with it to
+    /// avoid reallocating its counts array every interval.
+    pub fn get_interval_histogram_into(&self, target: &mut SimpleHdrHistogram<u64>) {
+        self.perform_interval_sample(|finished| finished.snapshot_into(target));
+    }
+
+    /// Discard everything recorded in the current interval.
+    pub fn reset(&self) {
+        // The inactive histogram is reset on each swap, so two swaps reset both.
+        self.perform_interval_sample(|_| {});
+        self.perform_interval_sample(|_| {});
+    }
+
+    /// Swap the active histogram for a freshly reset one, wait for recordings into the old one
+    /// to finish, then pass it to `use_finished` while still holding the reader lock (so that
+    /// another swap can't reset it in the meantime).
+    fn perform_interval_sample<F: FnOnce(&AtomicHistogram)>(&self, use_finished: F) {
+        let reader = self.phaser.reader_lock();
+
+        let finished_index = self.active_index.load(Ordering::SeqCst);
+        let next_index = 1 - finished_index;
+        // Nothing has recorded into the inactive histogram since the last flip completed, so it
+        // can be reset without losing anything.
+        self.histograms[next_index].reset();
+        self.active_index.store(next_index, Ordering::SeqCst);
+        reader.flip_phase();
+
+        use_finished(&self.histograms[finished_index]);
+    }
+}
@@ -0,0 +1,118 @@
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::thread;
+
+use hdr_histogram::simple_hdr_histogram::*;
+
+#[test]
+fn intervals_contain_values_recorded_since_previous() {
+    let recorder = Recorder::new(1, 3600 * 1000 * 1000, 3);
+    recorder.record_single_value(10).unwrap();
+    recorder.record_value_with_count(20, 2).unwrap();
+
+    let first = recorder.get_interval_histogram();
+    recorder.record_single_value(30).unwrap();
+    let second = recorder.get_interval_histogram();
+    let third = recorder.get_interval_histogram();
+
+    assert_eq!(3, first.get_count());
+    assert_eq!(2, first.get_count_at_value(20).unwrap());
+    assert_eq!(20, first.get_max());
+    assert_eq!(1, second.get_count());
+    assert_eq!(1, second.get_count_at_value(30).unwrap());
+    assert_eq!(0, second.get_count_at_value(10).unwrap());
+    assert_eq!(0, third.get_count());
+}
+
+#[test]
+fn get_interval_histogram_into_recycles_counts() {
+    let recorder = Recorder::new(1, 100_000, 3);
+    recorder.record_single_value(10).unwrap();
+    let mut interval = recorder.get_interval_histogram();
+    let counts_ptr = interval.counts.as_ptr();
+
+    recorder.record_single_value(500).unwrap();
+    recorder.get_interval_histogram_into(&mut interval);
+
+    assert_eq!(counts_ptr, interval.counts.as_ptr());
+    assert_eq!(1, interval.get_count());
+    assert_eq!(0, interval.get_count_at_value(10).unwrap());
+    assert_eq!(1, interval.get_count_at_value(500).unwrap());
+}
+
+#[test]
+fn reset_discards_current_interval() {
+    let recorder = Recorder::new(1, 100_000, 3);
+    recorder.record_single_value(10).unwrap();
+
+    recorder.reset();
+    recorder.record_single_value(20).unwrap();
+    let interval = recorder.get_interval_histogram();
+
+    assert_eq!(1, interval.get_count());
+    assert_eq!(1, interval.get_count_at_value(20).unwrap());
+}
+
+#[test]
+fn out_of_range_policy() {
+    let mut recorder = Recorder::new(1, 1000, 3);
+    assert_eq!(Err(RecordError::ValueOutOfRange), recorder.record_single_value(1 << 20));
+
+    recorder.set_out_of_range_policy(OutOfRangePolicy::Drop);
+    recorder.record_single_value(1 << 20).unwrap();
+    let first = recorder.get_interval_histogram();
+    recorder.record_single_value(1 << 20).unwrap();
+    let second = recorder.get_interval_histogram();
+
+    assert_eq!(1, first.get_out_of_range_count());
+    assert_eq!(1, second.get_out_of_range_count());
+}
+
+#[test]
+fn try_new_invalid() {
+    assert_eq!(CreationError::HighLessThanTwiceLow, Recorder::try_new(10, 15, 3).unwrap_err());
+}
+
+#[test]
+fn concurrent_recording_loses_nothing() {
+    let recorder = Arc::new(Recorder::new(1, 3600 * 1000 * 1000, 3));
+    let done = Arc::new(AtomicBool::new(false));
+
+    let writers: Vec<_> = (0..4).map(|t| {
+        let recorder = recorder.clone();
+        thread::spawn(move || {
+            for _ in 0..50_000 {
+                recorder.record_single_value(t + 1).unwrap();
+            }
+        })
+    }).collect();
+
+    let reader = {
+        let recorder = recorder.clone();
+        let done = done.clone();
+        thread::spawn(move || {
+            let mut total = SimpleHdrHistogram::<u64>::new(1, 3600 * 1000 * 1000, 3);
+            let mut interval = recorder.get_interval_histogram();
+            total.add(&interval).unwrap();
+            while !done.load(Ordering::SeqCst) {
+                recorder.get_interval_histogram_into(&mut interval);
+                total.add(&interval).unwrap();
+                thread::yield_now();
+            }
+            total
+        })
+    };
+
+    for w in writers {
+        w.join().unwrap();
+    }
+    done.store(true, Ordering::SeqCst);
+    let mut total = reader.join().unwrap();
+    total.add(&recorder.get_interval_histogram()).unwrap();
+
+    assert_eq!(200_000, total.get_count());
+    for t in 0..4 {
+        assert_eq!(50_000, total.get_count_at_value(t + 1).unwrap());
+    }
+}
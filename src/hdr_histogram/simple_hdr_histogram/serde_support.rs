@@ -0,0 +1,102 @@
+use serde::de;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+
+use hdr_histogram::simple_hdr_histogram::*;
+
+/// The serialized form of a histogram: its configuration and settings, plus only the non-zero
+/// counts as (logical index, count) pairs in index order. The counts array length is included so
+/// that deserializing can check it describes the same layout.
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "SimpleHdrHistogram")]
+struct SerializedHistogram<T> {
+    lowest_discernible_value: u64,
+    highest_trackable_value: u64,
+    num_significant_digits: u32,
+    auto_resize: bool,
+    out_of_range_policy: OutOfRangePolicy,
+    out_of_range_count: u128,
+    count_overflow_policy: CountOverflowPolicy,
+    saturated_count: u128,
+    value_unit_ratio: f64,
+    normalizing_index_offset: i32,
+    counts_len: usize,
+    counts: Vec<(usize, T)>,
+}
+
+impl<T: HistogramCount + Serialize, C: Counts<T>> Serialize for SimpleHdrHistogram<T, C> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let counts = (0..self.counts.len())
+            // index is within the counts array
+            .map(|i| (i, self.get_count_at_index(i).unwrap_or(T::zero())))
+            .filter(|&(_, count)| count != T::zero())
+            .collect();
+
+        SerializedHistogram {
+            lowest_discernible_value: self.lowest_discernible_value,
+            highest_trackable_value: self.highest_trackable_value,
+            num_significant_digits: self.num_significant_digits,
+            auto_resize: self.auto_resize,
+            out_of_range_policy: self.out_of_range_policy,
+            out_of_range_count: self.out_of_range_count,
+            count_overflow_policy: self.count_overflow_policy,
+            saturated_count: self.saturated_count,
+            value_unit_ratio: self.integer_to_double_value_conversion_ratio,
+            normalizing_index_offset: self.normalizing_index_offset,
+            counts_len: self.counts.len(),
+            counts: counts,
+        }.serialize(serializer)
+    }
+}
+
+impl<'de, T: HistogramCount + Deserialize<'de>, C: Counts<T>> Deserialize<'de>
+        for SimpleHdrHistogram<T, C> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = SerializedHistogram::<T>::deserialize(deserializer)?;
+
+        let mut h: SimpleHdrHistogram<T, C> = SimpleHdrHistogram::try_new_with_counts(
+                s.lowest_discernible_value, s.highest_trackable_value, s.num_significant_digits)
+            .map_err(|e| de::Error::custom(format_args!("invalid histogram parameters: {}", e)))?;
+        if s.counts_len != h.counts.len() {
+            return Err(de::Error::custom(format_args!(
+                "counts length {} does not match the {} implied by the histogram parameters",
+                s.counts_len, h.counts.len())));
+        }
+        if s.normalizing_index_offset.unsigned_abs() as usize >= h.counts.len() {
+            return Err(de::Error::custom("normalizing index offset is outside the counts array"));
+        }
+        if !(s.value_unit_ratio > 0.0 && s.value_unit_ratio.is_finite()) {
+            return Err(de::Error::custom(CreationError::InvalidValueUnitRatio));
+        }
+        h.auto_resize = s.auto_resize;
+        h.out_of_range_policy = s.out_of_range_policy;
+        h.out_of_range_count = s.out_of_range_count;
+        h.count_overflow_policy = s.count_overflow_policy;
+        h.saturated_count = s.saturated_count;
+        h.integer_to_double_value_conversion_ratio = s.value_unit_ratio;
+        h.normalizing_index_offset = s.normalizing_index_offset;
+
+        let mut total_count: u128 = 0;
+        let mut next_allowed_index = 0;
+        for (index, count) in s.counts {
+            if index < next_allowed_index || index >= h.counts.len() {
+                return Err(de::Error::custom(format_args!(
+                    "count index {} is out of order or outside the counts array", index)));
+            }
+            // we only use u8 - u128 types, so this must always work
+            total_count = total_count.checked_add(count.to_u128().unwrap())
+                .ok_or_else(|| de::Error::custom("total count overflows u128"))?;
+            // the index and the offset were both checked against the array length
+            let normalized_index = h.normalize_index(index, h.normalizing_index_offset,
+                                                     h.counts.len())
+                .map_err(de::Error::custom)?;
+            h.counts.set(normalized_index, count);
+            next_allowed_index = index + 1;
+        }
+        h.establish_internal_tracking_values();
+
+        Ok(h)
+    }
+}
@@ -0,0 +1,168 @@
+use serde_json;
+
+use hdr_histogram::simple_hdr_histogram::*;
+
+fn histo64(lowest_discernible_value: u64, highest_trackable_value: u64, num_significant_digits: u32)
+        -> SimpleHdrHistogram<u64> {
+    SimpleHdrHistogram::<u64>::new(lowest_discernible_value, highest_trackable_value, num_significant_digits)
+}
+
+fn json_with(field: &str, value: &str) -> String {
+    let h = histo64(1, 100_000, 3);
+    let mut json: serde_json::Value = serde_json::to_value(&h).unwrap();
+    json[field] = serde_json::from_str(value).unwrap();
+    json.to_string()
+}
+
+#[test]
+fn serialize_sparse_counts() {
+    let mut h = histo64(1, 100_000, 3);
+    h.set_out_of_range_policy(OutOfRangePolicy::Drop);
+    h.record_value_with_count(3, 2).unwrap();
+    h.record_single_value(5).unwrap();
+    h.record_single_value(1_000_000).unwrap();
+
+    let json: serde_json::Value = serde_json::to_value(&h).unwrap();
+
+    assert_eq!(serde_json::json!({
+        "lowest_discernible_value": 1,
+        "highest_trackable_value": 100_000,
+        "num_significant_digits": 3,
+        "auto_resize": false,
+        "out_of_range_policy": "Drop",
+        "out_of_range_count": 1,
+        "count_overflow_policy": "Error",
+        "saturated_count": 0,
+        "value_unit_ratio": 1.0,
+        "normalizing_index_offset": 0,
+        "counts_len": h.counts.len(),
+        "counts": [[3, 2], [5, 1]]
+    }), json);
+}
+
+#[test]
+fn round_trip() {
+    let mut h = histo64(1024, 1 << 40, 2);
+    h.set_auto_resize(true);
+    h.record_single_value(5000).unwrap();
+    h.record_value_with_count(1 << 30, 3).unwrap();
+    h.record_single_value(1 << 50).unwrap();
+
+    let json = serde_json::to_string(&h).unwrap();
+    let decoded: SimpleHdrHistogram<u64> = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(h.highest_trackable_value, decoded.highest_trackable_value);
+    assert_eq!(h.counts, decoded.counts);
+    assert_eq!(h.get_count(), decoded.get_count());
+    assert_eq!(h.highest_equivalent_value(h.get_max()), decoded.get_max());
+    assert_eq!(h.lowest_equivalent_value(h.get_min_non_zero()), decoded.get_min_non_zero());
+    assert!(decoded.is_auto_resize());
+}
+
+#[test]
+fn round_trip_value_unit_ratio() {
+    let h = HistogramBuilder::new(3).value_unit_ratio(0.001).build::<u64>().unwrap();
+
+    let json = serde_json::to_string(&h).unwrap();
+    let decoded: SimpleHdrHistogram<u64> = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(0.001, decoded.get_value_unit_ratio());
+}
+
+#[test]
+fn deserialize_invalid_value_unit_ratio() {
+    let err = serde_json::from_str::<SimpleHdrHistogram<u64>>(
+        &json_with("value_unit_ratio", "-1.0")).unwrap_err();
+
+    assert!(err.to_string().contains("value unit ratio"), "{}", err);
+}
+
+#[test]
+fn round_trip_u8() {
+    let mut h = SimpleHdrHistogram::<u8>::new(1, 1000, 1);
+    h.set_out_of_range_policy(OutOfRangePolicy::Clamp);
+    h.set_count_overflow_policy(CountOverflowPolicy::Saturate);
+    h.record_value_with_count(10, 255).unwrap();
+    h.record_value_with_count(10, 5).unwrap();
+
+    let json = serde_json::to_string(&h).unwrap();
+    let decoded: SimpleHdrHistogram<u8> = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(h.counts, decoded.counts);
+    assert_eq!(255, decoded.get_count());
+    assert_eq!(OutOfRangePolicy::Clamp, decoded.get_out_of_range_policy());
+    assert_eq!(CountOverflowPolicy::Saturate, decoded.get_count_overflow_policy());
+    assert_eq!(5, decoded.get_saturated_count());
+}
+
+#[test]
+fn deserialize_invalid_parameters() {
+    let err = serde_json::from_str::<SimpleHdrHistogram<u64>>(
+        &json_with("num_significant_digits", "6")).unwrap_err();
+
+    assert!(err.to_string().contains("number of significant digits"), "{}", err);
+}
+
+#[test]
+fn deserialize_counts_len_mismatch() {
+    let err = serde_json::from_str::<SimpleHdrHistogram<u64>>(
+        &json_with("highest_trackable_value", "1000000")).unwrap_err();
+
+    assert!(err.to_string().contains("counts length"), "{}", err);
+}
+
+#[test]
+fn deserialize_invalid_normalizing_index_offset() {
+    let err = serde_json::from_str::<SimpleHdrHistogram<u64>>(
+        &json_with("normalizing_index_offset", "1000000")).unwrap_err();
+
+    assert!(err.to_string().contains("normalizing index offset"), "{}", err);
+}
+
+#[test]
+fn deserialize_index_out_of_range() {
+    let err = serde_json::from_str::<SimpleHdrHistogram<u64>>(
+        &json_with("counts", "[[1, 1], [1000000, 1]]")).unwrap_err();
+
+    assert!(err.to_string().contains("count index 1000000"), "{}", err);
+}
+
+#[test]
+fn deserialize_index_out_of_order() {
+    let err = serde_json::from_str::<SimpleHdrHistogram<u64>>(
+        &json_with("counts", "[[5, 1], [5, 1]]")).unwrap_err();
+
+    assert!(err.to_string().contains("count index 5"), "{}", err);
+}
+
+#[test]
+fn deserialize_count_exceeds_type() {
+    let err = serde_json::from_str::<SimpleHdrHistogram<u8>>(
+        &json_with("counts", "[[5, 256]]")).unwrap_err();
+
+    assert!(err.to_string().contains("256"), "{}", err);
+}
+
+#[test]
+fn deserialize_total_count_overflow() {
+    // serde_json::Value can't hold a u128, so splice the max count into the text
+    let json = json_with("counts", "[[5, 0], [6, 1]]")
+        .replace("[5,0]", &format!("[5,{}]", u128::max_value()));
+    let err = serde_json::from_str::<SimpleHdrHistogram<u128>>(&json).unwrap_err();
+
+    assert!(err.to_string().contains("total count"), "{}", err);
+}
+
+#[test]
+fn serialize_iteration_value() {
+    let mut h = histo64(1, 100_000, 3);
+    h.record_value_with_count(7, 3).unwrap();
+
+    let v = h.recorded_values().into_iter().next().unwrap();
+    let json: serde_json::Value = serde_json::to_value(&v).unwrap();
+
+    assert_eq!(7, json["value_iterated_to"]);
+    assert_eq!(3, json["count_at_value_iterated_to"]);
+    assert_eq!(3, json["total_count_to_this_value"]);
+    assert_eq!(100.0, json["percentile"]);
+}
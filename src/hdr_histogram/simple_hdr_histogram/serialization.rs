@@ -0,0 +1,283 @@
+use std::io::Read;
+use std::io::Write;
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+
+use hdr_histogram::simple_hdr_histogram::*;
+
+/// Cookie base for the V2 encoding. The word size (always 8 in V2) is or'd into bits 4-7 of the
+/// cookie, so those are masked off when checking a cookie.
+const V2_COOKIE_BASE: u32 = 0x1c84_9303;
+const V2_COOKIE: u32 = V2_COOKIE_BASE | 0x10;
+const COOKIE_WORD_SIZE_MASK: u32 = 0xf0;
+
+/// Cookie base for a zlib compressed V2 encoding, as found in histogram logs.
+const V2_COMPRESSED_COOKIE_BASE: u32 = 0x1c84_9304;
+const V2_COMPRESSED_COOKIE: u32 = V2_COMPRESSED_COOKIE_BASE | 0x10;
+
+/// cookie and compressed length (4 bytes each)
+pub(super) const V2_COMPRESSED_HEADER_SIZE: usize = 8;
+
+/// The highest zlib compression level.
+pub const MAX_COMPRESSION_LEVEL: u32 = 9;
+
+/// cookie, payload length, normalizing index offset, significant digits (4 bytes each), lowest
+/// discernible value, highest trackable value, and integer to double conversion ratio (8 bytes
+/// each)
+pub(super) const V2_HEADER_SIZE: usize = 40;
+
+/// The longest a zig-zag LEB128 encoded i64 can be: 8 bytes of 7 bits plus one byte of 8 bits.
+const V2_MAX_VARINT_SIZE: usize = 9;
+
+impl<T: HistogramCount, C: Counts<T>> SimpleHdrHistogram<T, C> {
+    /// Encode this histogram in the V2 format used by the Java and C implementations, appending
+    /// it to `buf`. Returns the number of bytes written.
+    ///
+    /// Counts are written up to the one for the max value, with runs of zero counts collapsed.
+    /// Nothing is written to `buf` if encoding fails.
+    pub fn encode_into(&self, buf: &mut Vec<u8>) -> Result<usize, EncodeError> {
+        let start = buf.len();
+        // The payload length isn't known until the counts are written, so it's filled in after.
+        put_u32(buf, V2_COOKIE);
+        put_u32(buf, 0);
+        put_u32(buf, self.normalizing_index_offset as u32);
+        put_u32(buf, self.num_significant_digits);
+        put_u64(buf, self.lowest_discernible_value);
+        put_u64(buf, self.highest_trackable_value);
+        put_u64(buf, self.integer_to_double_value_conversion_ratio.to_bits());
+
+        if let Err(err) = self.encode_counts(buf) {
+            buf.truncate(start);
+            return Err(err);
+        }
+
+        // payload is at most 9 bytes per count, and there are few enough counts that it fits
+        let payload_len = (buf.len() - start - V2_HEADER_SIZE) as u32;
+        buf[start + 4..start + 8].copy_from_slice(&payload_len.to_be_bytes());
+
+        Ok(buf.len() - start)
+    }
+
+    /// Encode this histogram in the V2 format. See `encode_into`.
+    pub fn encode(&self) -> Result<Vec<u8>, EncodeError> {
+        let mut buf = Vec::with_capacity(V2_HEADER_SIZE + self.counts.len() * V2_MAX_VARINT_SIZE);
+        self.encode_into(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// Decode a histogram in the V2 format from the start of `bytes`. Any bytes after the
+    /// encoded histogram are ignored.
+    ///
+    /// The decoded histogram has the default auto-resize and out of range settings. Its max and
+    /// min are recalculated from the counts, so they are the highest and lowest equivalent values
+    /// of the original max and min.
+    pub fn decode(bytes: &[u8]) -> Result<SimpleHdrHistogram<T, C>, DecodeError> {
+        let mut pos = 0;
+        let cookie = get_u32(bytes, &mut pos)?;
+        if cookie & !COOKIE_WORD_SIZE_MASK != V2_COOKIE_BASE {
+            return Err(DecodeError::InvalidCookie);
+        }
+        let payload_len = get_u32(bytes, &mut pos)? as usize;
+        let normalizing_index_offset = get_u32(bytes, &mut pos)? as i32;
+        let num_significant_digits = get_u32(bytes, &mut pos)?;
+        let lowest_discernible_value = get_u64(bytes, &mut pos)?;
+        let highest_trackable_value = get_u64(bytes, &mut pos)?;
+        // only meaningful for double histograms
+        let conversion_ratio = f64::from_bits(get_u64(bytes, &mut pos)?);
+
+        let payload_end = V2_HEADER_SIZE.checked_add(payload_len).ok_or(DecodeError::Truncated)?;
+        if payload_end > bytes.len() {
+            return Err(DecodeError::Truncated);
+        }
+
+        let mut h: SimpleHdrHistogram<T, C> = SimpleHdrHistogram::try_new_with_counts(
+            lowest_discernible_value, highest_trackable_value, num_significant_digits)?;
+        // An offset can only come from shifting, which never moves counts a full array length.
+        if normalizing_index_offset.unsigned_abs() as usize >= h.counts.len() {
+            return Err(DecodeError::InvalidNormalizingIndexOffset);
+        }
+        h.normalizing_index_offset = normalizing_index_offset;
+        h.integer_to_double_value_conversion_ratio = conversion_ratio;
+
+        h.decode_counts(&bytes[V2_HEADER_SIZE..payload_end])?;
+        h.establish_internal_tracking_values();
+
+        Ok(h)
+    }
+
+    /// Encode this histogram in the compressed V2 format used in histogram logs, appending it to
+    /// `buf`. Returns the number of bytes written.
+    ///
+    /// `compression_level` ranges from 0 (no compression) to `MAX_COMPRESSION_LEVEL` (smallest
+    /// output); higher levels are treated as `MAX_COMPRESSION_LEVEL`. Nothing is written to `buf`
+    /// if encoding fails.
+    pub fn encode_compressed_into(&self, buf: &mut Vec<u8>, compression_level: u32)
+            -> Result<usize, EncodeError> {
+        let uncompressed = self.encode()?;
+
+        let start = buf.len();
+        put_u32(buf, V2_COMPRESSED_COOKIE);
+        put_u32(buf, 0);
+
+        let level = Compression::new(cmp::min(compression_level, MAX_COMPRESSION_LEVEL));
+        let mut encoder = ZlibEncoder::new(buf, level);
+        // writing to a Vec can't fail
+        encoder.write_all(&uncompressed).unwrap();
+        let buf = encoder.finish().unwrap();
+
+        // the compressed form of a histogram is far smaller than 4GiB
+        let compressed_len = (buf.len() - start - V2_COMPRESSED_HEADER_SIZE) as u32;
+        buf[start + 4..start + 8].copy_from_slice(&compressed_len.to_be_bytes());
+
+        Ok(buf.len() - start)
+    }
+
+    /// Encode this histogram in the compressed V2 format. See `encode_compressed_into`.
+    pub fn encode_compressed(&self, compression_level: u32) -> Result<Vec<u8>, EncodeError> {
+        let mut buf = Vec::new();
+        self.encode_compressed_into(&mut buf, compression_level)?;
+        Ok(buf)
+    }
+
+    /// Decode a histogram in the compressed V2 format from the start of `bytes`. Any bytes after
+    /// the encoded histogram are ignored.
+    ///
+    /// As with `decode`, the max and min are the highest and lowest equivalent values of the
+    /// original max and min.
+    pub fn decode_compressed(bytes: &[u8]) -> Result<SimpleHdrHistogram<T, C>, DecodeError> {
+        let mut pos = 0;
+        let cookie = get_u32(bytes, &mut pos)?;
+        if cookie & !COOKIE_WORD_SIZE_MASK != V2_COMPRESSED_COOKIE_BASE {
+            return Err(DecodeError::InvalidCookie);
+        }
+        let compressed_len = get_u32(bytes, &mut pos)? as usize;
+        let compressed_end = V2_COMPRESSED_HEADER_SIZE.checked_add(compressed_len)
+            .ok_or(DecodeError::Truncated)?;
+        if compressed_end > bytes.len() {
+            return Err(DecodeError::Truncated);
+        }
+
+        let mut uncompressed = Vec::new();
+        ZlibDecoder::new(&bytes[V2_COMPRESSED_HEADER_SIZE..compressed_end])
+            .read_to_end(&mut uncompressed)
+            .map_err(|_| DecodeError::DecompressionFailed)?;
+
+        SimpleHdrHistogram::decode(&uncompressed)
+    }
+
+    fn encode_counts(&self, buf: &mut Vec<u8>) -> Result<(), EncodeError> {
+        let counts_limit = cmp::min(self.counts_array_index(self.max_value) + 1, self.counts.len());
+        let mut index = 0;
+        while index < counts_limit {
+            // index is within the counts array
+            let count = self.get_count_at_index(index).unwrap_or(T::zero());
+            index += 1;
+
+            if count == T::zero() {
+                let mut zeros_count: i64 = 1;
+                while index < counts_limit &&
+                        self.get_count_at_index(index).unwrap_or(T::zero()) == T::zero() {
+                    zeros_count += 1;
+                    index += 1;
+                }
+                // A single zero is cheaper to write as itself than as a run.
+                put_zig_zag_varint(buf, if zeros_count > 1 { -zeros_count } else { 0 });
+            } else {
+                let count = match count.to_i64() {
+                    Some(c) => c,
+                    None => return Err(EncodeError::CountNotEncodable)
+                };
+                put_zig_zag_varint(buf, count);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn decode_counts(&mut self, payload: &[u8]) -> Result<(), DecodeError> {
+        let counts_len = self.counts.len();
+        let mut pos = 0;
+        let mut index = 0;
+        while pos < payload.len() {
+            let count = get_zig_zag_varint(payload, &mut pos)?;
+            if count < 0 {
+                // a run of zeros; the counts array is already zeroed
+                let zeros_count = count.unsigned_abs();
+                if zeros_count > (counts_len - index) as u64 {
+                    return Err(DecodeError::EncodedArrayTooLong);
+                }
+                index += zeros_count as usize;
+            } else {
+                if index >= counts_len {
+                    return Err(DecodeError::EncodedArrayTooLong);
+                }
+                let count = T::from_i64(count).ok_or(DecodeError::CountExceedsTypeMax)?;
+                // index is within the counts array and the offset was checked against its length
+                let normalized_index = self.normalize_index(index, self.normalizing_index_offset,
+                                                            counts_len)
+                    .map_err(|_| DecodeError::EncodedArrayTooLong)?;
+                self.counts.set(normalized_index, count);
+                index += 1;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn put_u32(buf: &mut Vec<u8>, value: u32) {
+    buf.extend_from_slice(&value.to_be_bytes());
+}
+
+fn put_u64(buf: &mut Vec<u8>, value: u64) {
+    buf.extend_from_slice(&value.to_be_bytes());
+}
+
+fn get_u32(bytes: &[u8], pos: &mut usize) -> Result<u32, DecodeError> {
+    let mut word = [0_u8; 4];
+    word.copy_from_slice(bytes.get(*pos..*pos + 4).ok_or(DecodeError::Truncated)?);
+    *pos += 4;
+    Ok(u32::from_be_bytes(word))
+}
+
+fn get_u64(bytes: &[u8], pos: &mut usize) -> Result<u64, DecodeError> {
+    let mut word = [0_u8; 8];
+    word.copy_from_slice(bytes.get(*pos..*pos + 8).ok_or(DecodeError::Truncated)?);
+    *pos += 8;
+    Ok(u64::from_be_bytes(word))
+}
+
+/// Write a zig-zag LEB128 varint. Unlike standard LEB128, the 9th byte (if reached) holds a full
+/// 8 bits, so no i64 takes more than 9 bytes.
+pub(super) fn put_zig_zag_varint(buf: &mut Vec<u8>, value: i64) {
+    let mut zig_zag = ((value << 1) ^ (value >> 63)) as u64;
+    for _ in 0..(V2_MAX_VARINT_SIZE - 1) {
+        if zig_zag >> 7 == 0 {
+            buf.push(zig_zag as u8);
+            return;
+        }
+        buf.push((zig_zag as u8 & 0x7f) | 0x80);
+        zig_zag >>= 7;
+    }
+    buf.push(zig_zag as u8);
+}
+
+/// Read a varint written by `put_zig_zag_varint`.
+pub(super) fn get_zig_zag_varint(bytes: &[u8], pos: &mut usize) -> Result<i64, DecodeError> {
+    let mut zig_zag = 0_u64;
+    for i in 0..V2_MAX_VARINT_SIZE {
+        let b = *bytes.get(*pos).ok_or(DecodeError::Truncated)?;
+        *pos += 1;
+        if i == V2_MAX_VARINT_SIZE - 1 {
+            zig_zag |= (b as u64) << 56;
+            break;
+        }
+        zig_zag |= ((b & 0x7f) as u64) << (7 * i);
+        if b & 0x80 == 0 {
+            break;
+        }
+    }
+
+    Ok((zig_zag >> 1) as i64 ^ -((zig_zag & 1) as i64))
+}
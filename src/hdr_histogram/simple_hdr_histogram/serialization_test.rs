@@ -0,0 +1,490 @@
+use base64;
+
+use hdr_histogram::simple_hdr_histogram::*;
+use hdr_histogram::simple_hdr_histogram::serialization::*;
+
+fn histo64(lowest_discernible_value: u64, highest_trackable_value: u64, num_significant_digits: u32)
+        -> SimpleHdrHistogram<u64> {
+    SimpleHdrHistogram::<u64>::new(lowest_discernible_value, highest_trackable_value, num_significant_digits)
+}
+
+fn assert_same_contents<T: HistogramCount + ::std::fmt::Debug>(expected: &SimpleHdrHistogram<T>,
+                                                             actual: &SimpleHdrHistogram<T>) {
+    assert_eq!(expected.lowest_discernible_value, actual.lowest_discernible_value);
+    assert_eq!(expected.highest_trackable_value, actual.highest_trackable_value);
+    assert_eq!(expected.num_significant_digits, actual.num_significant_digits);
+    assert_eq!(expected.normalizing_index_offset, actual.normalizing_index_offset);
+    assert_eq!(expected.counts, actual.counts);
+    assert_eq!(expected.get_count(), actual.get_count());
+    // min and max are only as precise as the counts they're reconstructed from
+    if expected.get_count() == 0 {
+        assert_eq!(expected.get_max(), actual.get_max());
+        assert_eq!(expected.get_min_non_zero(), actual.get_min_non_zero());
+    } else {
+        assert_eq!(expected.highest_equivalent_value(expected.get_max()), actual.get_max());
+        assert_eq!(expected.lowest_equivalent_value(expected.get_min_non_zero()),
+                   actual.get_min_non_zero());
+    }
+}
+
+#[test]
+fn encode_empty_matches_java() {
+    let h = histo64(1, 100_000, 3);
+
+    let expected = vec![
+        0x1c, 0x84, 0x93, 0x13, // cookie
+        0x00, 0x00, 0x00, 0x01, // payload length
+        0x00, 0x00, 0x00, 0x00, // normalizing index offset
+        0x00, 0x00, 0x00, 0x03, // significant digits
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, // lowest discernible value
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x86, 0xa0, // highest trackable value
+        0x3f, 0xf0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // conversion ratio 1.0
+        0x00 // the count at value 0
+    ];
+
+    assert_eq!(expected, h.encode().unwrap());
+}
+
+/// HdrHistogram's Java implementation's V2 encoding of the values 0 to 99,999, each recorded once,
+/// in a histogram with lowest 1, highest Long.MAX_VALUE and 3 significant digits. This is the
+/// `tests/data/seq-nums.hist` fixture from the Rust `hdrhistogram` crate's test data.
+const JAVA_SEQ_NUMS_V2: &[u8] = include_bytes!("../../../tests/data/seq-nums.hist");
+
+fn seq_nums_histogram() -> SimpleHdrHistogram<u64> {
+    let mut h = histo64(1, i64::max_value() as u64, 3);
+    for value in 0..100_000 {
+        h.record_single_value(value).unwrap();
+    }
+    h
+}
+
+#[test]
+fn decode_java_v2() {
+    let h = SimpleHdrHistogram::<u64>::decode(JAVA_SEQ_NUMS_V2).unwrap();
+
+    assert_same_contents(&seq_nums_histogram(), &h);
+    assert_eq!(i64::max_value() as u64, h.get_highest_trackable_value());
+    assert_eq!(100_000, h.get_count());
+    assert_eq!(1, h.get_count_at_value(1234).unwrap());
+    assert_eq!(2, h.get_count_at_value(2048).unwrap());
+    assert_eq!(h.highest_equivalent_value(99_999), h.get_max());
+    assert_eq!(h.highest_equivalent_value(50_000), h.get_value_at_percentile(50.0));
+    assert_eq!(h.highest_equivalent_value(99_000), h.get_value_at_percentile(99.0));
+}
+
+#[test]
+fn encode_matches_java_v2() {
+    assert_eq!(JAVA_SEQ_NUMS_V2, &seq_nums_histogram().encode().unwrap()[..]);
+}
+
+/// V2 encoding of `LARGE_COUNTS_VALUES` in a histogram with lowest 1, highest 3,600,000,000 and 3
+/// significant digits, covering multi-byte counts, single zeros and runs of zeros.
+const LARGE_COUNTS_V2: &[u8] = &[
+    0x1c, 0x84, 0x93, 0x13, 0x00, 0x00, 0x00, 0x1f, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,
+    0x00, 0x00, 0x00, 0x00, 0xd6, 0x93, 0xa4, 0x00, 0x3f, 0xf0, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x02, 0x06, 0x00, 0x04, 0xbf, 0x01, 0x80, 0x80,
+    0x80, 0x80, 0x80, 0x40, 0x85, 0x0e, 0xc8, 0x01, 0xab, 0x10, 0x02, 0x0a,
+    0x8b, 0x28, 0x0e, 0xb1, 0x66, 0xd8, 0x04, 0xa3, 0xbc, 0x01, 0x02,
+];
+
+/// (value, count) pairs encoded in `LARGE_COUNTS_V2`
+const LARGE_COUNTS_VALUES: &[(u64, u64)] = &[
+    (0, 1), (1, 3), (3, 2), (100, 1 << 40), (1000, 100), (2047, 1), (2048, 5), (12_345, 7),
+    (1_000_000, 300), (3_600_000_000, 1)
+];
+
+#[test]
+fn decode_large_counts() {
+    let h = SimpleHdrHistogram::<u64>::decode(LARGE_COUNTS_V2).unwrap();
+
+    assert_eq!(3_600_000_000, h.highest_trackable_value);
+    for &(value, count) in LARGE_COUNTS_VALUES {
+        assert_eq!(count, h.get_count_at_value(value).unwrap(), "{}", value);
+    }
+    assert_eq!(0, h.get_count_at_value(2).unwrap());
+    assert_eq!((1 << 40) + 420, h.get_count());
+    assert_eq!(1, h.get_min_non_zero());
+    assert_eq!(h.highest_equivalent_value(3_600_000_000), h.get_max());
+    assert_eq!(100, h.get_value_at_percentile(99.9));
+    assert_eq!(h.highest_equivalent_value(1_000_000), h.get_value_at_percentile(99.999_999_99));
+}
+
+#[test]
+fn encode_large_counts() {
+    let mut h = histo64(1, 3_600_000_000, 3);
+    for &(value, count) in LARGE_COUNTS_VALUES {
+        h.record_value_with_count(value, count).unwrap();
+    }
+
+    assert_eq!(LARGE_COUNTS_V2, &h.encode().unwrap()[..]);
+}
+
+#[test]
+fn encode_collapses_zero_runs() {
+    let mut h = histo64(1, 100_000, 3);
+    h.record_value_with_count(3, 2).unwrap();
+    h.record_single_value(5).unwrap();
+
+    let encoded = h.encode().unwrap();
+
+    assert_eq!(&[0, 0, 0, 4], &encoded[4..8]);
+    // run of 3 zeros, 2, single zero, 1
+    assert_eq!(&[0x05, 0x04, 0x00, 0x02], &encoded[V2_HEADER_SIZE..]);
+}
+
+#[test]
+fn encode_into_appends_and_returns_length() {
+    let mut h = histo64(1, 100_000, 3);
+    h.record_single_value(1000).unwrap();
+    let mut buf = vec![0xff, 0xff];
+
+    let len = h.encode_into(&mut buf).unwrap();
+
+    assert_eq!(buf.len() - 2, len);
+    assert_eq!(&[0xff, 0xff], &buf[0..2]);
+    assert_eq!(h.encode().unwrap(), &buf[2..]);
+}
+
+#[test]
+fn encode_count_too_big() {
+    let mut h = histo64(1, 100_000, 3);
+    h.record_value_with_count(1000, u64::max_value()).unwrap();
+    let mut buf = vec![0xff];
+
+    assert_eq!(Err(EncodeError::CountNotEncodable), h.encode_into(&mut buf));
+    assert_eq!(vec![0xff], buf);
+}
+
+#[test]
+fn round_trip_empty() {
+    let h = histo64(1, 100_000, 3);
+
+    let decoded = SimpleHdrHistogram::<u64>::decode(&h.encode().unwrap()).unwrap();
+
+    assert_same_contents(&h, &decoded);
+}
+
+#[test]
+fn round_trip_many_values() {
+    let mut h = histo64(1, 3600 * 1000 * 1000, 3);
+    for i in 0..10_000 {
+        h.record_value_with_count(i * 1777, i % 7 + 1).unwrap();
+    }
+    h.record_value_with_count(3600 * 1000 * 1000, i64::max_value() as u64 / 2).unwrap();
+
+    let decoded = SimpleHdrHistogram::<u64>::decode(&h.encode().unwrap()).unwrap();
+
+    assert_same_contents(&h, &decoded);
+}
+
+#[test]
+fn round_trip_unit_magnitude() {
+    let mut h = histo64(1024, 1 << 40, 2);
+    h.record_single_value(5000).unwrap();
+    h.record_value_with_count(1 << 30, 3).unwrap();
+
+    let decoded = SimpleHdrHistogram::<u64>::decode(&h.encode().unwrap()).unwrap();
+
+    assert_same_contents(&h, &decoded);
+}
+
+#[test]
+fn round_trip_u8() {
+    let mut h = SimpleHdrHistogram::<u8>::new(1, 100_000, 2);
+    h.record_value_with_count(7, 255).unwrap();
+    h.record_single_value(100_000).unwrap();
+
+    let decoded = SimpleHdrHistogram::<u8>::decode(&h.encode().unwrap()).unwrap();
+
+    assert_same_contents(&h, &decoded);
+}
+
+#[test]
+fn round_trip_auto_resized() {
+    let mut h = histo64(1, 2, 3);
+    h.set_auto_resize(true);
+    h.record_single_value(u64::max_value()).unwrap();
+
+    let decoded = SimpleHdrHistogram::<u64>::decode(&h.encode().unwrap()).unwrap();
+
+    assert_same_contents(&h, &decoded);
+    assert_eq!(1, decoded.get_count_at_value(u64::max_value()).unwrap());
+}
+
+#[test]
+fn decode_ignores_trailing_bytes() {
+    let mut h = histo64(1, 100_000, 3);
+    h.record_single_value(1000).unwrap();
+    let mut encoded = h.encode().unwrap();
+    encoded.extend_from_slice(&[1, 2, 3]);
+
+    let decoded = SimpleHdrHistogram::<u64>::decode(&encoded).unwrap();
+
+    assert_same_contents(&h, &decoded);
+}
+
+#[test]
+fn decode_accepts_any_word_size_in_cookie() {
+    let mut encoded = histo64(1, 100_000, 3).encode().unwrap();
+    encoded[3] = 0x03;
+
+    assert!(SimpleHdrHistogram::<u64>::decode(&encoded).is_ok());
+}
+
+#[test]
+fn decode_invalid_cookie() {
+    let mut encoded = histo64(1, 100_000, 3).encode().unwrap();
+    encoded[0] = 0x1d;
+
+    assert_eq!(DecodeError::InvalidCookie,
+               SimpleHdrHistogram::<u64>::decode(&encoded).unwrap_err());
+}
+
+#[test]
+fn decode_truncated_header() {
+    let encoded = histo64(1, 100_000, 3).encode().unwrap();
+
+    assert_eq!(DecodeError::Truncated,
+               SimpleHdrHistogram::<u64>::decode(&encoded[0..V2_HEADER_SIZE - 1]).unwrap_err());
+}
+
+#[test]
+fn decode_truncated_payload() {
+    let mut h = histo64(1, 100_000, 3);
+    h.record_single_value(1000).unwrap();
+    let encoded = h.encode().unwrap();
+
+    assert_eq!(DecodeError::Truncated,
+               SimpleHdrHistogram::<u64>::decode(&encoded[0..encoded.len() - 1]).unwrap_err());
+}
+
+#[test]
+fn decode_truncated_varint() {
+    let mut h = histo64(1, 100_000, 3);
+    h.record_value_with_count(0, 1000).unwrap();
+    let mut encoded = h.encode().unwrap();
+    // claim the payload ends in the middle of the 2-byte count
+    encoded[7] = 1;
+
+    assert_eq!(DecodeError::Truncated,
+               SimpleHdrHistogram::<u64>::decode(&encoded).unwrap_err());
+}
+
+#[test]
+fn decode_invalid_parameters() {
+    let mut encoded = histo64(1, 100_000, 3).encode().unwrap();
+    encoded[15] = 6;
+
+    assert_eq!(DecodeError::InvalidParameters(CreationError::SigFigExceedsMax),
+               SimpleHdrHistogram::<u64>::decode(&encoded).unwrap_err());
+}
+
+#[test]
+fn decode_invalid_normalizing_index_offset() {
+    let mut encoded = histo64(1, 100_000, 3).encode().unwrap();
+    encoded[8..12].copy_from_slice(&[0x7f, 0xff, 0xff, 0xff]);
+
+    assert_eq!(DecodeError::InvalidNormalizingIndexOffset,
+               SimpleHdrHistogram::<u64>::decode(&encoded).unwrap_err());
+}
+
+#[test]
+fn decode_too_many_counts() {
+    let mut h = histo64(1, 100_000, 3);
+    h.record_single_value(100_000).unwrap();
+    let mut encoded = h.encode().unwrap();
+    // shrink the declared highest trackable value to 1000
+    encoded[24..32].copy_from_slice(&[0, 0, 0, 0, 0, 0, 0x03, 0xe8]);
+
+    assert_eq!(DecodeError::EncodedArrayTooLong,
+               SimpleHdrHistogram::<u64>::decode(&encoded).unwrap_err());
+}
+
+#[test]
+fn decode_count_exceeds_type_max() {
+    let mut h = histo64(1, 100_000, 3);
+    h.record_value_with_count(1000, 256).unwrap();
+
+    assert_eq!(DecodeError::CountExceedsTypeMax,
+               SimpleHdrHistogram::<u8>::decode(&h.encode().unwrap()).unwrap_err());
+}
+
+#[test]
+fn zig_zag_varint_encodings() {
+    let cases: Vec<(i64, Vec<u8>)> = vec![
+        (0, vec![0x00]),
+        (-1, vec![0x01]),
+        (1, vec![0x02]),
+        (63, vec![0x7e]),
+        (64, vec![0x80, 0x01]),
+        (-65, vec![0x81, 0x01]),
+        (i64::max_value(), vec![0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]),
+        (i64::min_value(), vec![0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]),
+    ];
+
+    for (value, expected) in cases {
+        let mut buf = Vec::new();
+        put_zig_zag_varint(&mut buf, value);
+        assert_eq!(expected, buf, "encoding {}", value);
+
+        let mut pos = 0;
+        assert_eq!(value, get_zig_zag_varint(&buf, &mut pos).unwrap());
+        assert_eq!(buf.len(), pos);
+    }
+}
+
+/// HdrHistogram's Java implementation's compressed encoding of the same histogram as
+/// `JAVA_SEQ_NUMS_V2`. This is the `tests/data/seq-nums.histz` fixture from the Rust `hdrhistogram`
+/// crate's test data.
+const JAVA_SEQ_NUMS_COMPRESSED: &[u8] = include_bytes!("../../../tests/data/seq-nums.histz");
+
+/// The first interval histogram in jHiccup's `tagged-Log.logV2.hlog`, from HdrHistogram's Java
+/// test resources: 741 latencies in nanoseconds with lowest 20,000, highest 3,600,000,000,000 and
+/// 2 significant digits. Its log line gives the max as 2.769 ms.
+const JHICCUP_COMPRESSED_BASE64: &str =
+    "HISTFAAAAEV42pNpmSzMwMCgyAABTBDKT4GBgdnNYMcCBvsPEBEJISEuATEZMQ4uASkhIR4nrxg9v2lMaxhvMekILGZkKmcCAEf2CsI=";
+
+#[test]
+fn decode_java_compressed() {
+    let h = SimpleHdrHistogram::<u64>::decode_compressed(JAVA_SEQ_NUMS_COMPRESSED).unwrap();
+
+    assert_same_contents(&seq_nums_histogram(), &h);
+    assert_eq!(JAVA_SEQ_NUMS_V2, &h.encode().unwrap()[..]);
+}
+
+#[test]
+fn decode_jhiccup_compressed() {
+    let compressed = base64::decode(JHICCUP_COMPRESSED_BASE64).unwrap();
+
+    let h = SimpleHdrHistogram::<u64>::decode_compressed(&compressed).unwrap();
+
+    assert_eq!(20_000, h.lowest_discernible_value);
+    assert_eq!(3_600_000_000_000, h.highest_trackable_value);
+    assert_eq!(2, h.num_significant_digits);
+    assert_eq!(741, h.get_count());
+    assert_eq!(2_768_895, h.get_max());
+    assert_eq!(16_384, h.get_min_non_zero());
+    assert_eq!(173, h.get_count_at_value(376_831).unwrap());
+    assert_eq!(344_063, h.get_value_at_percentile(50.0));
+    assert_eq!(409_599, h.get_value_at_percentile(99.0));
+}
+
+#[test]
+fn round_trip_java_compressed() {
+    let compressed = base64::decode(JHICCUP_COMPRESSED_BASE64).unwrap();
+    let h = SimpleHdrHistogram::<u64>::decode_compressed(&compressed).unwrap();
+
+    // zlib implementations needn't produce the same bytes, so compare what they decode to
+    let decoded = SimpleHdrHistogram::<u64>::decode_compressed(&h.encode_compressed(6).unwrap())
+        .unwrap();
+
+    assert_same_contents(&h, &decoded);
+    assert_eq!(741, decoded.get_count());
+}
+
+#[test]
+fn encode_compressed_header() {
+    let mut h = histo64(1, 100_000, 3);
+    h.record_single_value(1000).unwrap();
+
+    let encoded = h.encode_compressed(MAX_COMPRESSION_LEVEL).unwrap();
+
+    assert_eq!(&[0x1c, 0x84, 0x93, 0x14], &encoded[0..4]);
+    let compressed_len = (encoded.len() - V2_COMPRESSED_HEADER_SIZE) as u32;
+    assert_eq!(&compressed_len.to_be_bytes(), &encoded[4..8]);
+    // zlib header
+    assert_eq!(0x78, encoded[8]);
+}
+
+#[test]
+fn encode_compressed_into_appends_and_returns_length() {
+    let mut h = histo64(1, 100_000, 3);
+    h.record_single_value(1000).unwrap();
+    let mut buf = vec![0xff];
+
+    let len = h.encode_compressed_into(&mut buf, 6).unwrap();
+
+    assert_eq!(buf.len() - 1, len);
+    assert_eq!(h.encode_compressed(6).unwrap(), &buf[1..]);
+}
+
+#[test]
+fn encode_compressed_count_too_big() {
+    let mut h = histo64(1, 100_000, 3);
+    h.record_value_with_count(1000, u64::max_value()).unwrap();
+    let mut buf = vec![0xff];
+
+    assert_eq!(Err(EncodeError::CountNotEncodable), h.encode_compressed_into(&mut buf, 6));
+    assert_eq!(vec![0xff], buf);
+}
+
+#[test]
+fn round_trip_compressed_all_levels() {
+    let mut h = histo64(1, 3600 * 1000 * 1000, 3);
+    for i in 0..10_000 {
+        h.record_value_with_count(i * 1777, i % 7 + 1).unwrap();
+    }
+
+    let mut sizes = Vec::new();
+    for level in 0..(MAX_COMPRESSION_LEVEL + 2) {
+        let encoded = h.encode_compressed(level).unwrap();
+        sizes.push(encoded.len());
+
+        let decoded = SimpleHdrHistogram::<u64>::decode_compressed(&encoded).unwrap();
+
+        assert_same_contents(&h, &decoded);
+    }
+
+    assert!(sizes[0] > sizes[MAX_COMPRESSION_LEVEL as usize]);
+    // levels above the max are clamped
+    assert_eq!(sizes[MAX_COMPRESSION_LEVEL as usize], sizes[MAX_COMPRESSION_LEVEL as usize + 1]);
+}
+
+#[test]
+fn decode_compressed_ignores_trailing_bytes() {
+    let mut h = histo64(1, 100_000, 3);
+    h.record_single_value(1000).unwrap();
+    let mut encoded = h.encode_compressed(6).unwrap();
+    encoded.extend_from_slice(&[1, 2, 3]);
+
+    let decoded = SimpleHdrHistogram::<u64>::decode_compressed(&encoded).unwrap();
+
+    assert_same_contents(&h, &decoded);
+}
+
+#[test]
+fn decode_compressed_rejects_uncompressed() {
+    let encoded = histo64(1, 100_000, 3).encode().unwrap();
+
+    assert_eq!(DecodeError::InvalidCookie,
+               SimpleHdrHistogram::<u64>::decode_compressed(&encoded).unwrap_err());
+}
+
+#[test]
+fn decode_rejects_compressed() {
+    let encoded = histo64(1, 100_000, 3).encode_compressed(6).unwrap();
+
+    assert_eq!(DecodeError::InvalidCookie,
+               SimpleHdrHistogram::<u64>::decode(&encoded).unwrap_err());
+}
+
+#[test]
+fn decode_compressed_truncated() {
+    let encoded = histo64(1, 100_000, 3).encode_compressed(6).unwrap();
+
+    assert_eq!(DecodeError::Truncated,
+               SimpleHdrHistogram::<u64>::decode_compressed(&encoded[0..encoded.len() - 1])
+                   .unwrap_err());
+}
+
+#[test]
+fn decode_compressed_corrupt() {
+    let mut encoded = histo64(1, 100_000, 3).encode_compressed(6).unwrap();
+    // break the zlib header check bits
+    encoded[9] ^= 0xff;
+
+    assert_eq!(DecodeError::DecompressionFailed,
+               SimpleHdrHistogram::<u64>::decode_compressed(&encoded).unwrap_err());
+}
+
@@ -0,0 +1,145 @@
+use hdr_histogram::simple_hdr_histogram::*;
+
+impl<T: HistogramCount, C: Counts<T>> SimpleHdrHistogram<T, C> {
+    /// Multiply every recorded value by 2^`binary_orders_of_magnitude`. Recorded zeros stay at 0.
+    ///
+    /// Shifting a value left by one binary order of magnitude moves its index up by one half
+    /// bucket, so this only changes the normalizing index offset rather than moving counts. The
+    /// exception is the lower half of bucket 0, which is finer grained than every other half
+    /// bucket; any counts there are moved individually.
+    ///
+    /// Fails with `ShiftError::Overflow` without modifying the histogram if the max value would be
+    /// shifted beyond the counts array. Auto-resizing histograms don't grow to make room.
+    pub fn shift_values_left(&mut self, binary_orders_of_magnitude: u32)
+            -> Result<(), ShiftError> {
+        if binary_orders_of_magnitude == 0 || self.only_zeros_recorded() {
+            return Ok(());
+        }
+        let shift_amount = self.shift_amount(binary_orders_of_magnitude)
+            .ok_or(ShiftError::Overflow)?;
+        if self.counts_array_index(self.max_value) >= self.counts.len() - shift_amount {
+            return Err(ShiftError::Overflow);
+        }
+
+        let max_value_before_shift = self.max_value;
+        let min_non_zero_value_before_shift = self.min_non_zero_value;
+        let lowest_half_bucket_populated = min_non_zero_value_before_shift
+            < (self.sub_bucket_half_count as u64) << self.unit_magnitude;
+
+        // shift_amount < counts.len(), which fits in i32
+        self.shift_normalizing_index_by_offset(shift_amount as i32, lowest_half_bucket_populated,
+                                               binary_orders_of_magnitude);
+
+        self.max_value = 0;
+        self.min_non_zero_value = u64::max_value();
+        // the max value's index was checked above, so these don't overflow
+        self.update_min_and_max(max_value_before_shift << binary_orders_of_magnitude);
+        if min_non_zero_value_before_shift != u64::max_value() {
+            self.update_min_and_max(min_non_zero_value_before_shift << binary_orders_of_magnitude);
+        }
+        Ok(())
+    }
+
+    /// Divide every recorded value by 2^`binary_orders_of_magnitude`. Recorded zeros stay at 0.
+    ///
+    /// This is the reverse of `shift_values_left`, and likewise only changes the normalizing
+    /// index offset.
+    ///
+    /// Fails with `ShiftError::Underflow` without modifying the histogram if any non-zero value
+    /// would be shifted into the lower half of bucket 0 or below, since it would lose precision
+    /// there.
+    pub fn shift_values_right(&mut self, binary_orders_of_magnitude: u32)
+            -> Result<(), ShiftError> {
+        if binary_orders_of_magnitude == 0 || self.only_zeros_recorded() {
+            return Ok(());
+        }
+        let shift_amount = self.shift_amount(binary_orders_of_magnitude)
+            .ok_or(ShiftError::Underflow)?;
+        if self.counts_array_index(self.min_non_zero_value)
+                < shift_amount + self.sub_bucket_half_count {
+            return Err(ShiftError::Underflow);
+        }
+
+        let max_value_before_shift = self.max_value;
+        let min_non_zero_value_before_shift = self.min_non_zero_value;
+
+        // shift_amount < counts.len(), which fits in i32
+        self.shift_normalizing_index_by_offset(-(shift_amount as i32), false,
+                                               binary_orders_of_magnitude);
+
+        self.max_value = 0;
+        self.min_non_zero_value = u64::max_value();
+        self.update_min_and_max(max_value_before_shift >> binary_orders_of_magnitude);
+        self.update_min_and_max(min_non_zero_value_before_shift >> binary_orders_of_magnitude);
+        Ok(())
+    }
+
+    /// True if there is nothing to shift, since 0 stays put.
+    fn only_zeros_recorded(&self) -> bool {
+        // index 0 is always in the counts array, and we only use u8 - u128 types, so the
+        // conversion must always work
+        self.total_count == self.get_count_at_index(0).unwrap_or(T::zero()).to_u128().unwrap()
+    }
+
+    /// How far indexes move when shifting by `binary_orders_of_magnitude`, or None if that's the
+    /// whole counts array or more, in which case any non-zero value would be shifted out of it.
+    fn shift_amount(&self, binary_orders_of_magnitude: u32) -> Option<usize> {
+        let half_buckets = self.counts.len() >> self.sub_bucket_half_count_magnitude;
+        if binary_orders_of_magnitude as usize >= half_buckets {
+            return None;
+        }
+        Some((binary_orders_of_magnitude as usize) << self.sub_bucket_half_count_magnitude)
+    }
+
+    fn shift_normalizing_index_by_offset(&mut self, offset_to_add: i32,
+                                         lowest_half_bucket_populated: bool,
+                                         binary_orders_of_magnitude: u32) {
+        // The count for 0 doesn't move, so take it out while the offset changes. Index 0 is always
+        // in the counts array.
+        let len = self.counts.len();
+        let pre_shift_zero_index =
+            self.normalize_index(0, self.normalizing_index_offset, len).unwrap_or(0);
+        let zero_value_count = self.counts.get(pre_shift_zero_index);
+        self.counts.set(pre_shift_zero_index, T::zero());
+
+        // Only the offset modulo the array length matters, and keeping it in [0, len) means
+        // normalize_index never has to wrap more than once, however many shifts there are.
+        self.normalizing_index_offset =
+            (self.normalizing_index_offset + offset_to_add).rem_euclid(len as i32);
+
+        if lowest_half_bucket_populated {
+            self.shift_lowest_half_bucket_contents_left(binary_orders_of_magnitude,
+                                                        pre_shift_zero_index);
+        }
+
+        let zero_index = self.normalize_index(0, self.normalizing_index_offset, len).unwrap_or(0);
+        self.counts.set(zero_index, zero_value_count);
+    }
+
+    /// Unlike every other half bucket, the lower half of bucket 0 can't be shifted by changing the
+    /// offset, so re-record its counts at their shifted values.
+    ///
+    /// Everything below the current lowest half bucket is empty (the shift would have overflowed
+    /// otherwise), and each shifted value's index is lower than its original index, so going up
+    /// from the bottom never overwrites a count that hasn't been moved yet.
+    fn shift_lowest_half_bucket_contents_left(&mut self, binary_orders_of_magnitude: u32,
+                                              pre_shift_zero_index: usize) {
+        let len = self.counts.len();
+        // slot 0 holds the count for 0, which doesn't move
+        for from_index in 1..self.sub_bucket_half_count {
+            let from_slot = (pre_shift_zero_index + from_index) % len;
+            let count = self.counts.get(from_slot);
+            if count == T::zero() {
+                continue;
+            }
+            self.counts.set(from_slot, T::zero());
+
+            let to_value = self.value_from_index(from_index) << binary_orders_of_magnitude;
+            let to_index = self.counts_array_index(to_value);
+            // to_index is below from_index + shift, which is within the counts array
+            let to_slot = self.normalize_index(to_index, self.normalizing_index_offset, len)
+                .unwrap_or(from_slot);
+            self.counts.set(to_slot, count);
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use hdr_histogram::simple_hdr_histogram::*;
+
+fn histo64(lowest_discernible_value: u64, highest_trackable_value: u64, num_significant_digits: u32)
+        -> SimpleHdrHistogram<u64> {
+    SimpleHdrHistogram::<u64>::new(lowest_discernible_value, highest_trackable_value, num_significant_digits)
+}
+
+#[test]
+fn shift_left_moves_values() {
+    let mut h = histo64(1, 1 << 30, 3);
+    h.record_value_with_count(0, 2).unwrap();
+    h.record_single_value(3000).unwrap();
+    h.record_value_with_count(100_000, 3).unwrap();
+
+    h.shift_values_left(4).unwrap();
+
+    assert_eq!(6, h.get_count());
+    assert_eq!(2, h.get_count_at_value(0).unwrap());
+    assert_eq!(1, h.get_count_at_value(3000 << 4).unwrap());
+    assert_eq!(3, h.get_count_at_value(100_000 << 4).unwrap());
+    assert_eq!(0, h.get_count_at_value(3000).unwrap());
+    assert_eq!(h.lowest_equivalent_value(3000 << 4), h.get_min_non_zero());
+    assert_eq!(h.highest_equivalent_value(100_000 << 4), h.highest_equivalent_value(h.get_max()));
+}
+
+#[test]
+fn shift_left_moves_lowest_half_bucket() {
+    let mut h = histo64(1, 1 << 30, 3);
+    h.record_single_value(1).unwrap();
+    h.record_single_value(700).unwrap();
+    h.record_single_value(5000).unwrap();
+
+    h.shift_values_left(3).unwrap();
+
+    assert_eq!(1, h.get_count_at_value(8).unwrap());
+    assert_eq!(1, h.get_count_at_value(5600).unwrap());
+    assert_eq!(1, h.get_count_at_value(40_000).unwrap());
+    assert_eq!(0, h.get_count_at_value(1).unwrap());
+    assert_eq!(0, h.get_count_at_value(700).unwrap());
+    assert_eq!(8, h.get_min_non_zero());
+}
+
+#[test]
+fn shift_right_undoes_shift_left() {
+    let mut h = histo64(1, 1 << 30, 3);
+    for &v in &[0, 3000, 70_000, 1 << 20] {
+        h.record_single_value(v).unwrap();
+    }
+    let counts_before = h.counts.clone();
+
+    h.shift_values_left(5).unwrap();
+    h.shift_values_right(5).unwrap();
+
+    let counts_after: Vec<u64> = (0..h.counts.len()).map(|i| h.get_count_at_index(i).unwrap())
+        .collect();
+    assert_eq!(counts_before, counts_after);
+    assert_eq!(3000, h.get_min_non_zero());
+}
+
+#[test]
+fn shift_left_overflow_is_error() {
+    let mut h = histo64(1, 1 << 20, 3);
+    h.record_single_value(1 << 18).unwrap();
+
+    assert_eq!(Err(ShiftError::Overflow), h.shift_values_left(3));
+    assert_eq!(1, h.get_count_at_value(1 << 18).unwrap());
+    assert_eq!(0, h.normalizing_index_offset);
+}
+
+#[test]
+fn shift_right_underflow_is_error() {
+    let mut h = histo64(1, 1 << 20, 3);
+    h.record_single_value(3000).unwrap();
+
+    assert_eq!(Err(ShiftError::Underflow), h.shift_values_right(2));
+    assert_eq!(1, h.get_count_at_value(3000).unwrap());
+    assert_eq!(0, h.normalizing_index_offset);
+}
+
+#[test]
+fn shift_only_zeros_does_nothing() {
+    let mut h = histo64(1, 1 << 20, 3);
+    h.record_single_value(0).unwrap();
+
+    h.shift_values_left(40).unwrap();
+
+    assert_eq!(0, h.normalizing_index_offset);
+    assert_eq!(1, h.get_count_at_value(0).unwrap());
+}
+
+#[test]
+fn record_and_iterate_after_shift() {
+    let mut h = histo64(1, 1 << 30, 3);
+    h.record_single_value(5000).unwrap();
+    h.shift_values_left(2).unwrap();
+    h.record_single_value(7).unwrap();
+    h.record_single_value(100_000).unwrap();
+
+    let values: Vec<u64> = h.recorded_values().into_iter().map(|v| v.value_iterated_to).collect();
+
+    assert_eq!(vec![7, h.highest_equivalent_value(20_000), h.highest_equivalent_value(100_000)],
+               values);
+    assert_eq!(h.highest_equivalent_value(20_000), h.get_value_at_percentile(50.0));
+}
+
+#[test]
+fn resize_after_shift_keeps_counts() {
+    let mut h = histo64(1, 1 << 20, 3);
+    h.set_auto_resize(true);
+    h.record_single_value(5000).unwrap();
+    h.shift_values_left(3).unwrap();
+
+    h.record_single_value(1 << 40).unwrap();
+
+    assert_eq!(1, h.get_count_at_value(40_000).unwrap());
+    assert_eq!(1, h.get_count_at_value(1 << 40).unwrap());
+    assert_eq!(2, h.get_count());
+}
+
+#[test]
+fn shift_by_whole_array_is_error() {
+    let mut h = histo64(1, 1 << 20, 3);
+    h.record_single_value(3000).unwrap();
+
+    assert_eq!(Err(ShiftError::Overflow), h.shift_values_left(1000));
+    assert_eq!(Err(ShiftError::Underflow), h.shift_values_right(1000));
+    assert_eq!(1, h.get_count_at_value(3000).unwrap());
+}
+
+#[test]
+fn queries_after_shift_right() {
+    let mut h = histo64(1, 1 << 30, 3);
+    for v in 1..101 {
+        h.record_single_value(v * 8192).unwrap();
+    }
+    let mut expected = histo64(1, 1 << 30, 3);
+    for v in 1..101 {
+        expected.record_single_value(v * 2048).unwrap();
+    }
+
+    h.shift_values_right(2).unwrap();
+
+    assert_eq!(expected.get_count(), h.get_count());
+    assert_eq!(expected.get_max(), h.get_max());
+    assert_eq!(expected.get_min_non_zero(), h.get_min_non_zero());
+    assert_eq!(expected.get_count_between(1000, 20_000), h.get_count_between(1000, 20_000));
+    assert_eq!(expected.get_percentile_below(5000), h.get_percentile_below(5000));
+    assert_eq!(expected.mean(), h.mean());
+    for &p in &[0.0, 25.0, 50.0, 99.0, 100.0] {
+        assert_eq!(expected.get_value_at_percentile(p), h.get_value_at_percentile(p), "{}", p);
+    }
+}
+
+fn iterated_values<I: IntoIterator<Item=HistogramIterationValue<u64>>>(iter: I)
+        -> Vec<(u64, u128, u128)> {
+    iter.into_iter()
+        .map(|v| (v.value_iterated_to, v.count_added_in_this_iteration_step,
+                  v.total_count_to_this_value))
+        .collect()
+}
+
+#[test]
+fn iterators_after_shift() {
+    let mut h = histo64(1, 1 << 30, 3);
+    let mut expected = histo64(1, 1 << 30, 3);
+    for &v in &[0, 5, 3000, 70_000, 1 << 20] {
+        h.record_single_value(v).unwrap();
+        expected.record_single_value(v << 3).unwrap();
+    }
+
+    h.shift_values_left(3).unwrap();
+
+    assert_eq!(iterated_values(expected.recorded_values()), iterated_values(h.recorded_values()));
+    assert_eq!(iterated_values(expected.all_values()), iterated_values(h.all_values()));
+    assert_eq!(iterated_values(expected.percentiles(5)), iterated_values(h.percentiles(5)));
+    assert_eq!(iterated_values(expected.linear_bucket_values(100_000)),
+               iterated_values(h.linear_bucket_values(100_000)));
+    assert_eq!(iterated_values(expected.logarithmic_bucket_values(1000, 2)),
+               iterated_values(h.logarithmic_bucket_values(1000, 2)));
+}
+
+#[test]
+fn add_shifted_histogram() {
+    let mut shifted = histo64(1, 1 << 30, 3);
+    shifted.record_value_with_count(3000, 2).unwrap();
+    shifted.shift_values_left(2).unwrap();
+    let mut h = histo64(1, 1 << 30, 3);
+    h.record_single_value(12_000).unwrap();
+
+    h.add(&shifted).unwrap();
+
+    assert_eq!(3, h.get_count_at_value(12_000).unwrap());
+    assert_eq!(3, h.get_count());
+}
+
+#[test]
+fn encode_round_trip_after_shift() {
+    let mut h = histo64(1, 1 << 30, 3);
+    h.record_single_value(3000).unwrap();
+    h.record_value_with_count(1 << 20, 4).unwrap();
+    h.shift_values_left(2).unwrap();
+
+    let decoded = SimpleHdrHistogram::<u64>::decode(&h.encode().unwrap()).unwrap();
+
+    assert_eq!(1, decoded.get_count_at_value(12_000).unwrap());
+    assert_eq!(4, decoded.get_count_at_value(1 << 22).unwrap());
+    assert_eq!(5, decoded.get_count());
+}
+
+#[test]
+fn shift_small_count_type() {
+    let mut h = SimpleHdrHistogram::<u8>::new(1, 100_000, 2);
+    h.record_value_with_count(50, 200).unwrap();
+
+    h.shift_values_left(6).unwrap();
+
+    assert_eq!(200, h.get_count_at_value(50 << 6).unwrap());
+    assert_eq!(h.lowest_equivalent_value(50 << 6), h.get_min_non_zero());
+}
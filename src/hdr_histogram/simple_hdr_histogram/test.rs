@@ -108,6 +108,833 @@ fn get_count_empty() {
     assert_eq!(1, h.get_count());
 }
 
+#[test]
+fn record_value_with_count_updates_count_min_and_max() {
+    let mut h = histo64(1, 100_000, 3);
+
+    h.record_value_with_count(3000, 40_000).unwrap();
+    h.record_value_with_count(5000, 2).unwrap();
+
+    assert_eq!(40_000, h.get_count_at_value(3000).unwrap());
+    assert_eq!(2, h.get_count_at_value(5000).unwrap());
+    assert_eq!(40_002, h.get_count());
+    assert_eq!(3000, h.get_min_non_zero());
+    assert_eq!(5000, h.get_max());
+}
+
+#[test]
+fn record_value_with_count_matches_repeated_single_records() {
+    let mut bulk = histo64(1, 100_000, 3);
+    let mut single = histo64(1, 100_000, 3);
+
+    bulk.record_value_with_count(100_000, 100).unwrap();
+    for _ in 0..100 {
+        single.record_single_value(100_000).unwrap();
+    }
+
+    assert_eq!(single.counts, bulk.counts);
+    assert_eq!(single.get_count(), bulk.get_count());
+    assert_eq!(single.get_max(), bulk.get_max());
+    assert_eq!(single.get_min_non_zero(), bulk.get_min_non_zero());
+}
+
+#[test]
+fn record_value_with_count_overflow_u8_leaves_histogram_unchanged() {
+    let mut h = SimpleHdrHistogram::<u8>::new(1, 100_000, 3);
+
+    h.record_value_with_count(1000, 200).unwrap();
+    assert_eq!(Err(RecordError::CountOverflow), h.record_value_with_count(1000, 56));
+
+    assert_eq!(200, h.get_count_at_value(1000).unwrap());
+    assert_eq!(200, h.get_count());
+
+    // exactly reaching the max is fine
+    h.record_value_with_count(1000, 55).unwrap();
+    assert_eq!(255, h.get_count_at_value(1000).unwrap());
+    assert_eq!(Err(RecordError::CountOverflow), h.record_single_value(1000));
+}
+
+#[test]
+fn record_value_with_count_overflow_u8_saturate() {
+    let mut h = SimpleHdrHistogram::<u8>::new(1, 100_000, 3);
+    h.set_count_overflow_policy(CountOverflowPolicy::Saturate);
+
+    h.record_value_with_count(1000, 200).unwrap();
+    h.record_value_with_count(1000, 100).unwrap();
+    h.record_single_value(1000).unwrap();
+    h.record_single_value(5000).unwrap();
+
+    assert_eq!(255, h.get_count_at_value(1000).unwrap());
+    assert_eq!(1, h.get_count_at_value(5000).unwrap());
+    assert_eq!(256, h.get_count());
+    assert_eq!(46, h.get_saturated_count());
+    assert_eq!(h.highest_equivalent_value(5000), h.get_value_at_percentile(100.0));
+}
+
+#[test]
+fn count_overflow_policy_preserved_by_new_from_and_reset_clears_saturated_count() {
+    let mut h = SimpleHdrHistogram::<u8>::new(1, 100_000, 3);
+    h.set_count_overflow_policy(CountOverflowPolicy::Saturate);
+    h.record_value_with_count(1000, 255).unwrap();
+    h.record_single_value(1000).unwrap();
+
+    let copy = SimpleHdrHistogram::new_from(&h);
+    assert_eq!(CountOverflowPolicy::Saturate, copy.get_count_overflow_policy());
+
+    h.reset();
+    assert_eq!(0, h.get_saturated_count());
+    assert_eq!(CountOverflowPolicy::Saturate, h.get_count_overflow_policy());
+}
+
+#[test]
+fn record_value_with_count_u128_beyond_u64() {
+    let mut h = SimpleHdrHistogram::<u128>::new(1, 100_000, 3);
+    let big = u64::max_value() as u128 * 4;
+
+    h.record_value_with_count(1000, big).unwrap();
+    h.record_value_with_count(5000, big).unwrap();
+    h.record_single_value(90_000).unwrap();
+
+    assert_eq!(big, h.get_count_at_value(1000).unwrap());
+    assert_eq!(big * 2 + 1, h.get_count());
+    assert_eq!(1000, h.get_value_at_percentile(49.0));
+    assert_eq!(h.highest_equivalent_value(5000), h.get_value_at_percentile(99.0));
+    assert_eq!(90_000, h.get_max());
+    assert_eq!(big * 2 + 1, h.get_count_between(0, 100_000));
+}
+
+#[test]
+fn total_count_of_max_u64_counts_does_not_overflow() {
+    let mut h = histo64(1, 1 << 50, 3);
+
+    h.record_value_with_count(1000, u64::max_value()).unwrap();
+    h.record_value_with_count(1 << 40, u64::max_value()).unwrap();
+
+    assert_eq!(u64::max_value() as u128 * 2, h.get_count());
+    assert_eq!(1000, h.get_value_at_percentile(49.0));
+    assert_eq!(h.highest_equivalent_value(1 << 40), h.get_value_at_percentile(51.0));
+    let mean = h.mean();
+    assert!((mean - (1000.0 + (1u64 << 40) as f64) / 2.0).abs() < mean * 0.001, "{}", mean);
+}
+
+#[test]
+fn record_value_with_expected_interval_back_fills_missing_samples() {
+    let mut h = histo64(1, 100_000, 3);
+
+    h.record_value_with_expected_interval(1000, 100).unwrap();
+
+    // 1000 itself plus 900, 800, ..., 100
+    assert_eq!(10, h.get_count());
+    for i in 1..11 {
+        assert_eq!(1, h.get_count_at_value(i * 100).unwrap());
+    }
+    assert_eq!(0, h.get_count_at_value(0).unwrap());
+    assert_eq!(100, h.get_min_non_zero());
+    assert_eq!(1000, h.get_max());
+}
+
+#[test]
+fn record_value_with_expected_interval_no_correction_when_value_within_interval() {
+    let mut h = histo64(1, 100_000, 3);
+
+    h.record_value_with_expected_interval(100, 100).unwrap();
+    h.record_value_with_expected_interval(50, 100).unwrap();
+    h.record_value_with_expected_interval(5000, 0).unwrap();
+
+    assert_eq!(3, h.get_count());
+}
+
+#[test]
+fn copy_corrected_for_coordinated_omission_matches_corrected_recording() {
+    let mut uncorrected = histo64(1, 100_000, 3);
+    let mut corrected = histo64(1, 100_000, 3);
+
+    for _ in 0..100 {
+        uncorrected.record_single_value(10).unwrap();
+        corrected.record_value_with_expected_interval(10, 10).unwrap();
+    }
+    // stay within the first bucket so all values have unit resolution
+    uncorrected.record_single_value(2000).unwrap();
+    corrected.record_value_with_expected_interval(2000, 10).unwrap();
+
+    let copy = uncorrected.copy_corrected_for_coordinated_omission(10).unwrap();
+
+    // source is untouched
+    assert_eq!(101, uncorrected.get_count());
+    // 100 + 200 values from 10 to 2000
+    assert_eq!(300, copy.get_count());
+    assert_eq!(corrected.counts, copy.counts);
+    assert_eq!(corrected.get_count(), copy.get_count());
+    assert_eq!(corrected.get_max(), copy.get_max());
+    assert_eq!(corrected.get_value_at_percentile(50.0), copy.get_value_at_percentile(50.0));
+}
+
+#[test]
+fn new_from_copies_configuration_but_not_counts() {
+    let mut h = histo64(4, 100_000, 2);
+    h.record_single_value(5000).unwrap();
+
+    let copy = SimpleHdrHistogram::new_from(&h);
+
+    assert_eq!(0, copy.get_count());
+    assert_eq!(h.counts.len(), copy.counts.len());
+    assert_eq!(h.unit_magnitude, copy.unit_magnitude);
+    assert_eq!(h.sub_bucket_count, copy.sub_bucket_count);
+}
+
+#[test]
+fn record_value_past_end_of_counts_is_error() {
+    let mut h = histo64(1, 100_000, 3);
+    let len = h.counts.len();
+
+    assert_eq!(Err(RecordError::ValueOutOfRange), h.record_single_value(1 << 40));
+
+    assert_eq!(len, h.counts.len());
+    assert_eq!(0, h.get_count());
+    assert_eq!(0, h.get_max());
+}
+
+#[test]
+fn record_value_past_end_of_counts_clamp() {
+    let mut h = histo64(1, 100_000, 3);
+    h.set_out_of_range_policy(OutOfRangePolicy::Clamp);
+
+    h.record_single_value(1 << 40).unwrap();
+
+    // top of 7th bucket
+    let top = (1 << 17) - 1;
+    assert_eq!(1, h.get_count());
+    assert_eq!(1, h.get_count_at_value(top).unwrap());
+    assert_eq!(top, h.get_max());
+    assert_eq!(0, h.get_out_of_range_count());
+}
+
+#[test]
+fn record_value_past_end_of_counts_drop() {
+    let mut h = histo64(1, 100_000, 3);
+    h.set_out_of_range_policy(OutOfRangePolicy::Drop);
+    let len = h.counts.len();
+
+    h.record_single_value(5000).unwrap();
+    h.record_single_value(1 << 40).unwrap();
+    h.record_value_with_count(u64::max_value(), 10).unwrap();
+
+    assert_eq!(len, h.counts.len());
+    assert_eq!(1, h.get_count());
+    assert_eq!(5000, h.get_max());
+    assert_eq!(11, h.get_out_of_range_count());
+}
+
+#[test]
+fn record_value_past_end_of_counts_auto_resize_overrides_policy() {
+    let mut h = histo64(1, 100_000, 3);
+    h.set_out_of_range_policy(OutOfRangePolicy::Drop);
+    h.set_auto_resize(true);
+
+    h.record_single_value(1 << 40).unwrap();
+
+    assert_eq!(1, h.get_count());
+    assert_eq!(0, h.get_out_of_range_count());
+}
+
+#[test]
+fn record_value_past_end_of_counts_auto_resize() {
+    let mut h = histo64(1, 100_000, 3);
+    h.set_auto_resize(true);
+    h.record_single_value(5000).unwrap();
+    h.record_single_value(100_000).unwrap();
+
+    h.record_single_value(1 << 40).unwrap();
+
+    // 2^40 is the start of the 30th bucket, so 30 + 1 buckets plus the bottom half of bucket 0
+    assert_eq!((30 + 2) * 1024, h.counts.len());
+    assert_eq!(3, h.get_count());
+    assert_eq!(1, h.get_count_at_value(5000).unwrap());
+    assert_eq!(1, h.get_count_at_value(100_000).unwrap());
+    assert_eq!(1, h.get_count_at_value(1 << 40).unwrap());
+    assert_eq!(1 << 40, h.get_max());
+    assert_eq!(h.highest_equivalent_value(1 << 40), h.get_value_at_percentile(100.0));
+    // top of the 30th bucket
+    assert_eq!((1 << 41) - 1, h.get_highest_trackable_value());
+}
+
+#[test]
+fn auto_resize_within_top_bucket_does_not_resize() {
+    let mut h = histo64(1, 100_000, 3);
+    h.set_auto_resize(true);
+    let len = h.counts.len();
+
+    // top bucket can hold up to 2^17 - 1 even though 100_000 was requested
+    h.record_single_value((1 << 17) - 1).unwrap();
+
+    assert_eq!(len, h.counts.len());
+    assert_eq!(100_000, h.get_highest_trackable_value());
+}
+
+#[test]
+fn highest_trackable_value_is_as_created() {
+    let mut h = histo64(1, 1000, 3);
+
+    h.record_single_value(2047).unwrap();
+
+    assert_eq!(1000, h.get_highest_trackable_value());
+    assert_eq!(Err(RecordError::ValueOutOfRange), h.record_single_value(2048));
+}
+
+#[test]
+fn auto_resize_and_out_of_range_policy_preserved_by_new_from() {
+    let mut h = histo64(1, 100_000, 3);
+    h.set_auto_resize(true);
+
+    h.set_out_of_range_policy(OutOfRangePolicy::Clamp);
+
+    let copy = SimpleHdrHistogram::new_from(&h);
+    assert!(copy.is_auto_resize());
+    assert_eq!(OutOfRangePolicy::Clamp, copy.get_out_of_range_policy());
+}
+
+#[test]
+fn add_same_layout() {
+    let mut h = histo64(1, 100_000, 3);
+    let mut other = histo64(1, 100_000, 3);
+
+    h.record_single_value(1000).unwrap();
+    h.record_single_value(5000).unwrap();
+    other.record_single_value(0).unwrap();
+    other.record_single_value(5000).unwrap();
+    other.record_value_with_count(100_000, 3).unwrap();
+
+    h.add(&other).unwrap();
+
+    assert_eq!(7, h.get_count());
+    assert_eq!(1, h.get_count_at_value(0).unwrap());
+    assert_eq!(1, h.get_count_at_value(1000).unwrap());
+    assert_eq!(2, h.get_count_at_value(5000).unwrap());
+    assert_eq!(3, h.get_count_at_value(100_000).unwrap());
+    assert_eq!(1000, h.get_min_non_zero());
+    assert_eq!(100_000, h.get_max());
+    // other is untouched
+    assert_eq!(5, other.get_count());
+}
+
+#[test]
+fn add_empty_doesnt_change_min_or_max() {
+    let mut h = histo64(1, 100_000, 3);
+    let other = histo64(1, 100_000, 3);
+    h.record_single_value(1000).unwrap();
+
+    h.add(&other).unwrap();
+
+    assert_eq!(1, h.get_count());
+    assert_eq!(1000, h.get_min_non_zero());
+    assert_eq!(1000, h.get_max());
+}
+
+#[test]
+fn add_same_layout_smaller_range() {
+    let mut h = histo64(1, 1_000_000, 3);
+    let mut other = histo64(1, 100_000, 3);
+    other.record_single_value(100_000).unwrap();
+
+    h.add(&other).unwrap();
+
+    assert_eq!(1, h.get_count_at_value(100_000).unwrap());
+    assert_eq!(100_000, h.get_max());
+}
+
+#[test]
+fn add_different_precision_re_records_values() {
+    let mut h = histo64(1, 100_000, 2);
+    let mut other = histo64(1, 100_000, 3);
+    other.record_single_value(100).unwrap();
+    other.record_value_with_count(50_000, 2).unwrap();
+
+    h.add(&other).unwrap();
+
+    assert_eq!(3, h.get_count());
+    assert_eq!(1, h.get_count_at_value(100).unwrap());
+    assert_eq!(2, h.get_count_at_value(50_000).unwrap());
+    // values from other are at its highest equivalent value
+    assert_eq!(other.highest_equivalent_value(50_000), h.get_max());
+}
+
+#[test]
+fn add_different_unit_magnitude_re_records_values() {
+    let mut h = histo64(1, 100_000, 3);
+    let mut other = histo64(1024, 100_000, 3);
+    other.record_single_value(2048).unwrap();
+
+    h.add(&other).unwrap();
+
+    // other's highest equivalent value for 2048
+    assert_eq!(1, h.get_count_at_value(2048 + 1023).unwrap());
+    assert_eq!(1, h.get_count());
+}
+
+#[test]
+fn add_other_max_exceeds_range() {
+    let mut h = histo64(1, 1000, 3);
+    let mut other = histo64(1, 100_000, 3);
+    h.record_single_value(10).unwrap();
+    other.record_single_value(50_000).unwrap();
+
+    assert_eq!(Err(AdditionError::OtherAddendValueExceedsRange), h.add(&other));
+    assert_eq!(1, h.get_count());
+}
+
+#[test]
+fn add_other_max_exceeds_range_auto_resize() {
+    let mut h = histo64(1, 1000, 3);
+    h.set_auto_resize(true);
+    let mut other = histo64(1, 100_000, 3);
+    other.record_single_value(50_000).unwrap();
+
+    h.add(&other).unwrap();
+
+    // 2048 * 2^5 > 50_000, so 6 buckets
+    assert_eq!((6 + 1) * 1024, h.counts.len());
+    assert_eq!(1, h.get_count_at_value(50_000).unwrap());
+}
+
+#[test]
+fn add_count_overflow_leaves_histogram_untouched() {
+    let mut h = SimpleHdrHistogram::<u8>::new(1, 100_000, 3);
+    let mut other = SimpleHdrHistogram::<u8>::new(1, 100_000, 3);
+    h.record_value_with_count(10, 1).unwrap();
+    h.record_value_with_count(5000, 200).unwrap();
+    other.record_value_with_count(10, 1).unwrap();
+    other.record_value_with_count(5000, 100).unwrap();
+
+    assert_eq!(Err(AdditionError::CountOverflow), h.add(&other));
+    assert_eq!(1, h.get_count_at_value(10).unwrap());
+    assert_eq!(201, h.get_count());
+}
+
+#[test]
+fn add_different_layout_count_overflow_leaves_histogram_untouched() {
+    let mut h = SimpleHdrHistogram::<u8>::new(1, 100_000, 2);
+    let mut other = SimpleHdrHistogram::<u8>::new(1, 100_000, 3);
+    h.record_single_value(10).unwrap();
+    // separate counts in other, but the same count in h
+    other.record_value_with_count(1000, 200).unwrap();
+    other.record_value_with_count(1001, 100).unwrap();
+
+    assert_eq!(Err(AdditionError::CountOverflow), h.add(&other));
+    assert_eq!(0, h.get_count_at_value(1000).unwrap());
+    assert_eq!(1, h.get_count());
+}
+
+#[test]
+fn add_count_overflow_saturate() {
+    let mut h = SimpleHdrHistogram::<u8>::new(1, 100_000, 3);
+    h.set_count_overflow_policy(CountOverflowPolicy::Saturate);
+    let mut other = SimpleHdrHistogram::<u8>::new(1, 100_000, 3);
+    h.record_value_with_count(1000, 200).unwrap();
+    other.record_value_with_count(1000, 100).unwrap();
+    other.record_single_value(5000).unwrap();
+
+    h.add(&other).unwrap();
+
+    assert_eq!(255, h.get_count_at_value(1000).unwrap());
+    assert_eq!(1, h.get_count_at_value(5000).unwrap());
+    assert_eq!(256, h.get_count());
+    assert_eq!(45, h.get_saturated_count());
+}
+
+#[test]
+fn add_out_of_range_counts() {
+    let mut h = histo64(1, 1000, 3);
+    let mut other = histo64(1, 1000, 3);
+    other.set_out_of_range_policy(OutOfRangePolicy::Drop);
+    other.record_single_value(1 << 20).unwrap();
+
+    h.add(&other).unwrap();
+
+    assert_eq!(1, h.get_out_of_range_count());
+}
+
+#[test]
+fn add_assign() {
+    let mut h = histo64(1, 100_000, 3);
+    let mut other = histo64(1, 100_000, 3);
+    other.record_single_value(5000).unwrap();
+
+    h += &other;
+    h += &other;
+
+    assert_eq!(2, h.get_count_at_value(5000).unwrap());
+}
+
+#[test]
+#[should_panic]
+fn add_assign_panics_on_failure() {
+    let mut h = histo64(1, 1000, 3);
+    let mut other = histo64(1, 100_000, 3);
+    other.record_single_value(50_000).unwrap();
+
+    h += &other;
+}
+
+#[test]
+fn extend_values_and_counts() {
+    let mut h = histo64(1, 100_000, 3);
+
+    h.extend(vec![10, 20, 10]);
+    h.extend(vec![(5000, 3), (10, 1)]);
+
+    assert_eq!(3, h.get_count_at_value(10).unwrap());
+    assert_eq!(1, h.get_count_at_value(20).unwrap());
+    assert_eq!(3, h.get_count_at_value(5000).unwrap());
+    assert_eq!(7, h.get_count());
+}
+
+#[test]
+fn extend_u64_counts_saturate() {
+    let mut h = SimpleHdrHistogram::<u8>::new(1, 100_000, 3);
+    h.set_count_overflow_policy(CountOverflowPolicy::Saturate);
+    h.record_single_value(20).unwrap();
+
+    h.extend(vec![(10, 300), (20, 1000), (30, 3)]);
+
+    assert_eq!(255, h.get_count_at_value(10).unwrap());
+    assert_eq!(255, h.get_count_at_value(20).unwrap());
+    assert_eq!(3, h.get_count_at_value(30).unwrap());
+    assert_eq!(513, h.get_count());
+    assert_eq!(45 + 746, h.get_saturated_count());
+    assert_eq!(10, h.get_min_non_zero());
+}
+
+#[test]
+#[should_panic(expected = "Could not record value 10: count at value would overflow")]
+fn extend_u64_count_overflow_panics() {
+    let mut h = SimpleHdrHistogram::<u8>::new(1, 100_000, 3);
+
+    h.extend(vec![(10, 256)]);
+}
+
+#[test]
+fn extend_u64_count_out_of_range_dropped() {
+    let mut h = SimpleHdrHistogram::<u8>::new(1, 100_000, 3);
+    h.set_out_of_range_policy(OutOfRangePolicy::Drop);
+
+    h.extend(vec![(1 << 40, 1000)]);
+
+    assert_eq!(0, h.get_count());
+    assert_eq!(1000, h.get_out_of_range_count());
+    assert_eq!(0, h.get_saturated_count());
+}
+
+#[test]
+#[should_panic(expected = "Could not record value 1000000")]
+fn extend_panics_on_failure() {
+    let mut h = histo64(1, 100_000, 3);
+
+    h.extend(vec![10, 1_000_000]);
+}
+
+#[test]
+fn new_from_iter_uses_layout() {
+    let mut layout = SimpleHdrHistogram::<u8>::new(1, 100_000, 2);
+    layout.set_out_of_range_policy(OutOfRangePolicy::Drop);
+    layout.record_single_value(7).unwrap();
+
+    let h = SimpleHdrHistogram::new_from_iter(&layout, (0..100).map(|i| i * 10)).unwrap();
+
+    assert_eq!(layout.counts.len(), h.counts.len());
+    assert_eq!(OutOfRangePolicy::Drop, h.get_out_of_range_policy());
+    assert_eq!(100, h.get_count());
+    assert_eq!(0, h.get_count_at_value(7).unwrap());
+    assert_eq!(990, h.get_max());
+}
+
+#[test]
+fn new_from_iter_record_error() {
+    let layout = SimpleHdrHistogram::<u8>::new(1, 100_000, 2);
+
+    assert_eq!(RecordError::CountOverflow,
+               SimpleHdrHistogram::new_from_iter(&layout, vec![5; 256]).unwrap_err());
+}
+
+#[test]
+fn sum_owned_and_borrowed() {
+    let histograms: Vec<SimpleHdrHistogram<u64>> = (1..4)
+        .map(|i| {
+            let mut h = histo64(1, 100_000, 3);
+            h.record_value_with_count(i * 1000, i).unwrap();
+            h
+        })
+        .collect();
+
+    let borrowed_sum: SimpleHdrHistogram<u64> = histograms.iter().sum();
+    let owned_sum: SimpleHdrHistogram<u64> = histograms.into_iter().sum();
+
+    for sum in &[borrowed_sum, owned_sum] {
+        assert_eq!(6, sum.get_count());
+        assert_eq!(2, sum.get_count_at_value(2000).unwrap());
+        assert_eq!(3000, sum.get_max());
+        assert_eq!(1000, sum.get_min_non_zero());
+    }
+}
+
+#[test]
+#[should_panic(expected = "Cannot sum zero histograms")]
+fn sum_empty_panics() {
+    let histograms: Vec<SimpleHdrHistogram<u64>> = Vec::new();
+
+    let _: SimpleHdrHistogram<u64> = histograms.iter().sum();
+}
+
+#[test]
+#[should_panic(expected = "Could not add histogram")]
+fn sum_beyond_first_range_panics() {
+    let mut wide = histo64(1, 1_000_000, 3);
+    wide.record_single_value(500_000).unwrap();
+    let histograms = [histo64(1, 1000, 3), wide];
+
+    let _: SimpleHdrHistogram<u64> = histograms.iter().sum();
+}
+
+#[test]
+fn try_sum() {
+    let mut first = histo64(1, 100_000, 3);
+    first.record_single_value(10).unwrap();
+    let mut second = histo64(1, 100_000, 2);
+    second.record_value_with_count(100, 2).unwrap();
+    let layout = histo64(1, 100_000, 3);
+
+    let sum = SimpleHdrHistogram::try_sum(&layout, &[first, second]).unwrap();
+
+    assert_eq!(3, sum.get_count());
+    assert_eq!(2, sum.get_count_at_value(100).unwrap());
+    assert_eq!(3, sum.num_significant_digits);
+}
+
+#[test]
+fn try_sum_empty() {
+    let mut layout = histo64(1, 100_000, 3);
+    layout.set_auto_resize(true);
+    layout.record_single_value(10).unwrap();
+    let histograms: Vec<SimpleHdrHistogram<u64>> = Vec::new();
+
+    let sum = SimpleHdrHistogram::try_sum(&layout, &histograms).unwrap();
+
+    assert_eq!(0, sum.get_count());
+    assert!(sum.is_auto_resize());
+    assert_eq!(100_000, sum.get_highest_trackable_value());
+}
+
+#[test]
+fn try_sum_beyond_layout_range() {
+    let mut wide = histo64(1, 1_000_000, 3);
+    wide.record_single_value(500_000).unwrap();
+    let histograms = [histo64(1, 1000, 3), wide];
+
+    assert_eq!(AdditionError::OtherAddendValueExceedsRange,
+               SimpleHdrHistogram::try_sum(&histograms[0], &histograms).unwrap_err());
+
+    let mut layout = histo64(1, 1000, 3);
+    layout.set_auto_resize(true);
+    let sum = SimpleHdrHistogram::try_sum(&layout, &histograms).unwrap();
+    assert_eq!(1, sum.get_count_at_value(500_000).unwrap());
+}
+
+#[test]
+fn reset_clears_without_reallocating() {
+    let mut h = histo64(1, 100_000, 3);
+    h.set_out_of_range_policy(OutOfRangePolicy::Drop);
+    h.record_single_value(10).unwrap();
+    h.record_value_with_count(5000, 3).unwrap();
+    h.record_single_value(1 << 40).unwrap();
+    let counts_ptr = h.counts.as_ptr();
+    let len = h.counts.len();
+
+    h.reset();
+
+    assert_eq!(counts_ptr, h.counts.as_ptr());
+    assert_eq!(len, h.counts.len());
+    assert!(h.counts.iter().all(|&c| c == 0));
+    assert_eq!(0, h.get_count());
+    assert_eq!(0, h.get_max());
+    assert_eq!(u64::max_value(), h.get_min_non_zero());
+    assert_eq!(0, h.get_out_of_range_count());
+    assert_eq!(0, h.normalizing_index_offset);
+    // configuration is kept
+    assert_eq!(OutOfRangePolicy::Drop, h.get_out_of_range_policy());
+
+    h.record_single_value(20).unwrap();
+    assert_eq!(1, h.get_count());
+    assert_eq!(20, h.get_max());
+    assert_eq!(20, h.get_min_non_zero());
+}
+
+#[test]
+fn copy_into_replaces_target_contents() {
+    let mut h = histo64(1, 100_000, 3);
+    let mut target = histo64(1, 100_000, 3);
+    h.record_single_value(10).unwrap();
+    h.record_value_with_count(5000, 3).unwrap();
+    target.record_single_value(100_000).unwrap();
+    let counts_ptr = target.counts.as_ptr();
+
+    h.copy_into(&mut target).unwrap();
+
+    assert_eq!(counts_ptr, target.counts.as_ptr());
+    assert_eq!(h.counts, target.counts);
+    assert_eq!(4, target.get_count());
+    assert_eq!(0, target.get_count_at_value(100_000).unwrap());
+    assert_eq!(5000, target.get_max());
+    assert_eq!(10, target.get_min_non_zero());
+}
+
+#[test]
+fn copy_into_too_small_target() {
+    let mut h = histo64(1, 100_000, 3);
+    let mut target = histo64(1, 1000, 3);
+    h.record_single_value(100_000).unwrap();
+    target.record_value_with_count(500, 2).unwrap();
+
+    assert_eq!(Err(AdditionError::OtherAddendValueExceedsRange), h.copy_into(&mut target));
+    assert_eq!(2, target.get_count());
+    assert_eq!(2, target.get_count_at_value(500).unwrap());
+}
+
+#[test]
+fn copy_into_auto_resizing_target() {
+    let mut h = histo64(1, 100_000, 3);
+    let mut target = histo64(1, 1000, 3);
+    target.set_auto_resize(true);
+    h.record_single_value(100_000).unwrap();
+    target.record_single_value(500).unwrap();
+
+    h.copy_into(&mut target).unwrap();
+
+    assert_eq!(1, target.get_count());
+    assert_eq!(1, target.get_count_at_value(100_000).unwrap());
+}
+
+#[test]
+fn subtract_same_layout() {
+    let mut h = histo64(1, 100_000, 3);
+    let mut other = histo64(1, 100_000, 3);
+    h.record_single_value(10).unwrap();
+    h.record_value_with_count(1000, 3).unwrap();
+    h.record_single_value(100_000).unwrap();
+    other.record_single_value(10).unwrap();
+    other.record_single_value(1000).unwrap();
+    other.record_single_value(100_000).unwrap();
+
+    h.subtract(&other).unwrap();
+
+    assert_eq!(2, h.get_count());
+    assert_eq!(0, h.get_count_at_value(10).unwrap());
+    assert_eq!(2, h.get_count_at_value(1000).unwrap());
+    assert_eq!(0, h.get_count_at_value(100_000).unwrap());
+    assert_eq!(1000, h.get_min_non_zero());
+    assert_eq!(1000, h.get_max());
+}
+
+#[test]
+fn subtract_everything_leaves_empty_histogram() {
+    let mut h = histo64(1, 100_000, 3);
+    h.record_single_value(0).unwrap();
+    h.record_single_value(5000).unwrap();
+    let other = h.copy_corrected_for_coordinated_omission(0).unwrap();
+
+    h.subtract(&other).unwrap();
+
+    assert_eq!(0, h.get_count());
+    assert_eq!(0, h.get_max());
+    assert_eq!(u64::max_value(), h.get_min_non_zero());
+}
+
+#[test]
+fn subtract_recalculates_max_as_highest_equivalent_value() {
+    let mut h = histo64(1, 100_000, 3);
+    let mut other = histo64(1, 100_000, 3);
+    h.record_single_value(5000).unwrap();
+    h.record_single_value(6000).unwrap();
+    other.record_single_value(6000).unwrap();
+
+    h.subtract(&other).unwrap();
+
+    assert_eq!(h.highest_equivalent_value(5000), h.get_max());
+    assert_eq!(h.lowest_equivalent_value(5000), h.get_min_non_zero());
+}
+
+#[test]
+fn subtract_different_precision() {
+    let mut h = histo64(1, 100_000, 2);
+    let mut other = histo64(1, 100_000, 3);
+    h.record_value_with_count(50_000, 3).unwrap();
+    // both map to the same less precise index in h
+    other.record_single_value(50_000).unwrap();
+    other.record_single_value(50_100).unwrap();
+
+    h.subtract(&other).unwrap();
+
+    assert_eq!(1, h.get_count());
+    assert_eq!(1, h.get_count_at_value(50_000).unwrap());
+}
+
+#[test]
+fn subtract_different_precision_count_exceeds_minuend_count() {
+    let mut h = histo64(1, 100_000, 2);
+    let mut other = histo64(1, 100_000, 3);
+    h.record_single_value(50_000).unwrap();
+    // each is <= h's count, but together they're not
+    other.record_single_value(50_000).unwrap();
+    other.record_single_value(50_100).unwrap();
+
+    assert_eq!(Err(SubtractionError::SubtrahendCountExceedsMinuendCount), h.subtract(&other));
+    assert_eq!(1, h.get_count_at_value(50_000).unwrap());
+}
+
+#[test]
+fn subtract_count_exceeds_minuend_count_leaves_histogram_untouched() {
+    let mut h = histo64(1, 100_000, 3);
+    let mut other = histo64(1, 100_000, 3);
+    h.record_single_value(10).unwrap();
+    h.record_single_value(1000).unwrap();
+    other.record_single_value(10).unwrap();
+    other.record_value_with_count(1000, 2).unwrap();
+
+    assert_eq!(Err(SubtractionError::SubtrahendCountExceedsMinuendCount), h.subtract(&other));
+    assert_eq!(1, h.get_count_at_value(10).unwrap());
+    assert_eq!(2, h.get_count());
+}
+
+#[test]
+fn subtract_other_max_exceeds_range() {
+    let mut h = histo64(1, 1000, 3);
+    let mut other = histo64(1, 100_000, 3);
+    other.record_single_value(50_000).unwrap();
+
+    assert_eq!(Err(SubtractionError::SubtrahendValueExceedsMinuendRange), h.subtract(&other));
+}
+
+#[test]
+fn sub_assign() {
+    let mut h = histo64(1, 100_000, 3);
+    let mut other = histo64(1, 100_000, 3);
+    h.record_value_with_count(5000, 3).unwrap();
+    other.record_single_value(5000).unwrap();
+
+    h -= &other;
+    h -= &other;
+
+    assert_eq!(1, h.get_count_at_value(5000).unwrap());
+}
+
+#[test]
+#[should_panic]
+fn sub_assign_panics_on_failure() {
+    let mut h = histo64(1, 100_000, 3);
+    let mut other = histo64(1, 100_000, 3);
+    other.record_single_value(5000).unwrap();
+
+    h -= &other;
+}
+
 #[test]
 fn get_min_non_zero_empty() {
     let h = histo64(1, 100_000, 3);
@@ -189,6 +1016,99 @@ fn get_max_empty() {
     assert_eq!(0, h.get_max());
 }
 
+#[test]
+fn mean_and_stdev_empty() {
+    let h = histo64(1, 100_000, 3);
+
+    assert_eq!(0.0, h.mean());
+    assert_eq!(0.0, h.stdev());
+}
+
+#[test]
+fn mean_and_stdev_unit_resolution() {
+    let mut h = histo64(1, 100_000, 3);
+    h.record_value_with_count(1000, 2).unwrap();
+    h.record_value_with_count(2000, 2).unwrap();
+
+    assert_eq!(1500.0, h.mean());
+    assert_eq!(500.0, h.stdev());
+}
+
+#[test]
+fn mean_and_stdev_use_median_equivalent_value() {
+    let mut h = histo64(1, 100_000, 3);
+    // in the 7th bucket, which has a scale of 64
+    h.record_single_value(100_000).unwrap();
+
+    let median_equivalent = h.median_equivalent_value(100_000);
+    assert_eq!(h.lowest_equivalent_value(100_000) + 32, median_equivalent);
+    assert_eq!(median_equivalent as f64, h.mean());
+    assert_eq!(0.0, h.stdev());
+}
+
+#[test]
+fn median_equivalent_value_unit_magnitude_0() {
+    let h = histo64(1, 100_000, 3);
+
+    assert_eq!(4, h.median_equivalent_value(4));
+    assert_eq!(2047, h.median_equivalent_value(2047));
+    // scale of 2 in 2nd bucket
+    assert_eq!(2049, h.median_equivalent_value(2048));
+    assert_eq!(2049, h.median_equivalent_value(2049));
+    // scale of 4 in 3rd bucket
+    assert_eq!(4098, h.median_equivalent_value(4099));
+}
+
+#[test]
+fn median() {
+    let mut h = histo64(1, 100_000, 3);
+    for i in 1..10 {
+        h.record_single_value(i * 100).unwrap();
+    }
+
+    assert_eq!(500, h.median());
+    assert_eq!(h.get_value_at_percentile(50.0), h.median());
+}
+
+#[test]
+fn get_count_between() {
+    let mut h = histo64(1, 100_000, 3);
+    h.record_single_value(10).unwrap();
+    h.record_value_with_count(1000, 3).unwrap();
+    h.record_single_value(5000).unwrap();
+    h.record_single_value(100_000).unwrap();
+
+    assert_eq!(6, h.get_count_between(0, 1_000_000));
+    assert_eq!(4, h.get_count_between(10, 1000));
+    assert_eq!(3, h.get_count_between(11, 1000));
+    // 5001 is equivalent to 5000 (scale of 4 in 3rd bucket)
+    assert_eq!(4, h.get_count_between(1000, 5001));
+    assert_eq!(0, h.get_count_between(20_000, 90_000));
+    assert_eq!(0, h.get_count_between(1 << 40, 1 << 50));
+}
+
+#[test]
+fn get_percentile_below() {
+    let mut h = histo64(1, 100_000, 3);
+    h.record_single_value(1000).unwrap();
+    h.record_single_value(2000).unwrap();
+    h.record_single_value(3000).unwrap();
+    h.record_single_value(4000).unwrap();
+
+    assert_eq!(0.0, h.get_percentile_below(999));
+    assert_eq!(25.0, h.get_percentile_below(1000));
+    assert_eq!(50.0, h.get_percentile_below(2999));
+    assert_eq!(100.0, h.get_percentile_below(4000));
+    assert_eq!(100.0, h.get_percentile_below(1 << 40));
+}
+
+#[test]
+fn get_percentile_below_empty() {
+    let h = histo64(1, 100_000, 3);
+
+    assert_eq!(100.0, h.get_percentile_below(1000));
+}
+
 #[test]
 fn get_value_at_percentile_empty() {
     let h = histo64(1, 100_000, 3);
@@ -764,6 +1684,14 @@ fn normalize_index_zero_offset_doesnt_change_index() {
     assert_eq!(1234, h.normalize_index(1234, 0, h.counts.len()).unwrap())
 }
 
+#[test]
+fn normalize_index_past_end_of_array_with_offset_is_error() {
+    let h = histo64(1, 100_000, 3);
+    let len = h.counts.len();
+
+    assert_eq!(Err(QueryError::IndexOutOfRange), h.normalize_index(len + 1, 1024, len));
+}
+
 #[test]
 fn normalize_index_simple() {
     let h = histo64(1, 10_000, 3);
@@ -1,3 +1,9 @@
+extern crate base64;
+extern crate flate2;
 extern crate num;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
 
 pub mod hdr_histogram;